@@ -0,0 +1,279 @@
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+const CREDENTIAL_PROMPT_EVENT: &str = "credential-prompt";
+
+/// Set for git, and through it for the app's own executable when git runs it as the
+/// askpass helper: `<address> <token>` of the listener waiting for prompts
+const ASKPASS_ENV: &str = "CODECRAFT_ASKPASS";
+
+/// How long a prompt waits for an answer before git is told there is none
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Prompts with these words ask for something that shouldn't be shown as it is typed
+const SECRET_WORDS: [&str; 3] = ["password", "passphrase", "token"];
+
+static NEXT_PROMPT_ID: AtomicU64 = AtomicU64::new(1);
+
+lazy_static::lazy_static! {
+    /// Address of the listener, once it is started
+    static ref ADDRESS: Mutex<Option<String>> = Mutex::new(None);
+    /// Running operations by token, with what to tag their prompts with
+    static ref SESSIONS: Mutex<HashMap<String, (AppHandle, String)>> = Mutex::new(HashMap::new());
+    /// Prompts waiting for `answer_credential_prompt`
+    static ref PENDING: Mutex<HashMap<u64, Sender<Option<String>>>> = Mutex::new(HashMap::new());
+}
+
+/// Payload of the `credential-prompt` event
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialPrompt {
+    pub id: u64,
+    /// Repository of the operation that needs the answer
+    pub repo_path: String,
+    /// What git or ssh asked, e.g. `Password for 'https://github.com':`
+    pub prompt: String,
+    pub secret: bool,
+}
+
+/// Routes the prompts of one git operation to the UI while it runs
+pub struct AskpassSession {
+    token: String,
+    env: Vec<(String, String)>,
+}
+
+impl AskpassSession {
+    pub fn start(app_handle: &AppHandle, repo_path: &str) -> Result<Self, String> {
+        let address = listen()?;
+        let exe = std::env::current_exe()
+            .map_err(|e| format!("Failed to locate the askpass helper: {}", e))?
+            .to_string_lossy()
+            .to_string();
+
+        let mut secret = [0u8; 16];
+        getrandom::fill(&mut secret)
+            .map_err(|e| format!("Failed to generate an askpass token: {}", e))?;
+        let token: String = secret.iter().map(|byte| format!("{:02x}", byte)).collect();
+        SESSIONS
+            .lock()
+            .insert(token.clone(), (app_handle.clone(), repo_path.to_string()));
+
+        let env = vec![
+            ("GIT_ASKPASS".to_string(), exe.clone()),
+            ("SSH_ASKPASS".to_string(), exe),
+            // Use the helper for ssh too, even with a terminal or no display
+            ("SSH_ASKPASS_REQUIRE".to_string(), "force".to_string()),
+            ("GIT_TERMINAL_PROMPT".to_string(), "0".to_string()),
+            (ASKPASS_ENV.to_string(), format!("{} {}", address, token)),
+        ];
+
+        Ok(AskpassSession { token, env })
+    }
+
+    /// Environment that points git and ssh at the helper
+    pub fn env(&self) -> &[(String, String)] {
+        &self.env
+    }
+}
+
+impl Drop for AskpassSession {
+    fn drop(&mut self) {
+        SESSIONS.lock().remove(&self.token);
+    }
+}
+
+/// Start the listener on first use and return its address
+fn listen() -> Result<String, String> {
+    let mut address = ADDRESS.lock();
+    if let Some(address) = address.as_ref() {
+        return Ok(address.clone());
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .map_err(|e| format!("Failed to start the askpass listener: {}", e))?;
+    let local = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to start the askpass listener: {}", e))?
+        .to_string();
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            thread::spawn(move || {
+                if let Err(e) = serve(stream) {
+                    eprintln!("Failed to answer credential prompt: {}", e);
+                }
+            });
+        }
+    });
+
+    *address = Some(local.clone());
+    Ok(local)
+}
+
+/// Answer one helper from the UI
+fn serve(stream: TcpStream) -> std::io::Result<()> {
+    exchange(stream, ask_ui)
+}
+
+/// Read `<token>\0<prompt>\0` from a helper, get the answer from `answer`, and reply
+/// with `1<answer>`, or `0` when there is none
+fn exchange(
+    stream: TcpStream,
+    answer: impl FnOnce(&str, String) -> Option<String>,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut token = Vec::new();
+    let mut prompt = Vec::new();
+    reader.read_until(0, &mut token)?;
+    reader.read_until(0, &mut prompt)?;
+    token.pop();
+    prompt.pop();
+
+    let answer = answer(
+        &String::from_utf8_lossy(&token),
+        String::from_utf8_lossy(&prompt).to_string(),
+    );
+    let reply = match answer {
+        Some(answer) => format!("1{}", answer),
+        None => "0".to_string(),
+    };
+    let mut stream = stream;
+    stream.write_all(reply.as_bytes())
+}
+
+/// Send `prompt` to the UI of the operation holding `token` and wait for the answer.
+/// `None` if the prompt was cancelled, not answered in time, or the token is unknown.
+fn ask_ui(token: &str, prompt: String) -> Option<String> {
+    let (app_handle, repo_path) = SESSIONS.lock().get(token).cloned()?;
+
+    let id = NEXT_PROMPT_ID.fetch_add(1, Ordering::Relaxed);
+    let (sender, answer) = mpsc::channel();
+    PENDING.lock().insert(id, sender);
+
+    let lowercase = prompt.to_lowercase();
+    let event = CredentialPrompt {
+        id,
+        repo_path,
+        secret: SECRET_WORDS.iter().any(|word| lowercase.contains(word)),
+        prompt,
+    };
+    let _ = app_handle.emit(CREDENTIAL_PROMPT_EVENT, event);
+
+    let answer = answer.recv_timeout(PROMPT_TIMEOUT).ok().flatten();
+    PENDING.lock().remove(&id);
+    answer
+}
+
+/// Ask the app listening at `target` (the value of `CODECRAFT_ASKPASS`) to answer
+/// `prompt`. `None` means there is no answer.
+pub fn ask(target: &str, prompt: &str) -> Option<String> {
+    let (address, token) = target.split_once(' ')?;
+    let mut stream = TcpStream::connect(address).ok()?;
+    stream
+        .write_all(format!("{}\0{}\0", token, prompt).as_bytes())
+        .ok()?;
+
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).ok()?;
+    reply.strip_prefix('1').map(str::to_string)
+}
+
+/// When git runs this executable as its askpass helper, answer the prompt it passed
+/// and return the exit code; `None` when running as the app
+pub fn helper() -> Option<i32> {
+    let target = std::env::var(ASKPASS_ENV).ok()?;
+    let prompt = std::env::args().nth(1).unwrap_or_default();
+
+    match ask(&target, &prompt) {
+        Some(answer) => {
+            println!("{}", answer);
+            Some(0)
+        }
+        None => Some(1),
+    }
+}
+
+/// Answer a `credential-prompt`; `None` cancels it
+#[tauri::command]
+pub async fn answer_credential_prompt(id: u64, response: Option<String>) -> Result<(), String> {
+    let sender = PENDING
+        .lock()
+        .remove(&id)
+        .ok_or_else(|| format!("Credential prompt {} is no longer waiting", id))?;
+    let _ = sender.send(response);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serve one helper connection with `answer` and return the `CODECRAFT_ASKPASS`
+    /// value pointing at it
+    fn one_shot(answer: fn(&str, String) -> Option<String>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            exchange(stream, answer).unwrap();
+        });
+        format!("{} secret-token", address)
+    }
+
+    #[test]
+    fn ask_gets_the_answer_for_its_token_and_prompt() {
+        let target = one_shot(|token, prompt| {
+            assert_eq!(token, "secret-token");
+            assert_eq!(prompt, "Password for 'https://example.com':");
+            Some("hunter2 with spaces".to_string())
+        });
+        assert_eq!(
+            ask(&target, "Password for 'https://example.com':"),
+            Some("hunter2 with spaces".to_string())
+        );
+    }
+
+    #[test]
+    fn ask_tells_an_empty_answer_from_none() {
+        let target = one_shot(|_, _| Some(String::new()));
+        assert_eq!(ask(&target, "Username:"), Some(String::new()));
+
+        let target = one_shot(|_, _| None);
+        assert_eq!(ask(&target, "Username:"), None);
+    }
+
+    #[test]
+    fn unknown_tokens_get_no_answer() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener.accept().unwrap().0).unwrap());
+
+        assert_eq!(
+            ask(&format!("{} not-a-session", address), "Password:"),
+            None
+        );
+    }
+
+    #[test]
+    fn malformed_targets_get_no_answer() {
+        assert_eq!(ask("no-token", "Password:"), None);
+    }
+
+    #[test]
+    fn answering_sends_to_the_waiting_prompt_once() {
+        let (sender, answer) = mpsc::channel();
+        PENDING.lock().insert(u64::MAX, sender);
+
+        tauri::async_runtime::block_on(answer_credential_prompt(u64::MAX, Some("yes".into())))
+            .unwrap();
+        assert_eq!(answer.recv().unwrap(), Some("yes".to_string()));
+        assert!(tauri::async_runtime::block_on(answer_credential_prompt(u64::MAX, None)).is_err());
+    }
+}
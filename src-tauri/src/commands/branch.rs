@@ -0,0 +1,185 @@
+use crate::commands::commit::CommitInfo;
+use crate::git::with_backend;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Namespace holding the tips of archived branches, out of sight of `git branch`
+const ARCHIVE_NAMESPACE: &str = "refs/codecraft/archive/";
+
+/// A local or remote-tracking branch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Branch {
+    /// Short name, e.g. `main` or `origin/main`
+    pub name: String,
+    pub is_remote: bool,
+    /// Upstream of a local branch, e.g. `origin/main`
+    pub upstream: Option<String>,
+    /// Worktree the branch is checked out in, if any
+    pub worktree_path: Option<String>,
+    pub last_commit: CommitInfo,
+}
+
+/// Tip of a deleted branch kept under `refs/codecraft/archive/<branch>/<time>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedBranch {
+    pub ref_name: String,
+    pub branch: String,
+    /// Seconds since the Unix epoch
+    pub archived_at: u64,
+    pub commit: CommitInfo,
+}
+
+/// Number of single-character edits that turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Branch names close to `rev`, best match first
+fn suggestions(rev: &str, branches: &[Branch]) -> Vec<String> {
+    let needle = rev.to_lowercase();
+    let max_distance = (needle.chars().count() / 3).max(2);
+
+    let mut ranked: Vec<(usize, &str)> = branches
+        .iter()
+        .filter_map(|branch| {
+            let name = branch.name.to_lowercase();
+            let distance = edit_distance(&needle, &name);
+            let close = distance <= max_distance
+                || name.contains(&needle)
+                || (needle.len() > 2 && needle.contains(&name));
+            close.then_some((distance, branch.name.as_str()))
+        })
+        .collect();
+
+    ranked.sort();
+    ranked.dedup_by(|a, b| a.1 == b.1);
+    ranked
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// Resolve `rev` to a commit, or fail with the branch names it may have meant
+pub fn resolve_strict(repo_path: &Path, rev: &str) -> Result<String, String> {
+    if let Ok(sha) = with_backend(|git| git.resolve_commit(repo_path, rev)) {
+        return Ok(sha);
+    }
+
+    let branches = with_backend(|git| git.list_branches(repo_path)).unwrap_or_default();
+    match suggestions(rev, &branches).as_slice() {
+        [] => Err(format!("Unknown branch or revision '{}'", rev)),
+        close => Err(format!(
+            "Unknown branch or revision '{}'. Did you mean: {}?",
+            rev,
+            close.join(", ")
+        )),
+    }
+}
+
+/// List local branches followed by remote-tracking branches, with the worktree each
+/// local branch is checked out in
+#[tauri::command]
+pub async fn list_branches(repo_path: String) -> Result<Vec<Branch>, String> {
+    let repo = Path::new(&repo_path);
+    let mut branches = with_backend(|git| git.list_branches(repo))?;
+    let worktrees = with_backend(|git| git.list_worktrees(repo))?;
+
+    for branch in branches.iter_mut().filter(|branch| !branch.is_remote) {
+        branch.worktree_path = worktrees
+            .iter()
+            .find(|wt| wt.branch.as_ref() == Some(&branch.name))
+            .map(|wt| wt.path.clone());
+    }
+
+    Ok(branches)
+}
+
+/// Keep the tip of `branch_name` reachable after the branch is deleted. Returns the
+/// archive ref.
+pub fn archive_branch(repo_path: &Path, branch_name: &str) -> Result<String, String> {
+    let tip =
+        with_backend(|git| git.resolve_commit(repo_path, &format!("refs/heads/{}", branch_name)))?;
+    let archived_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let ref_name = format!("{}{}/{}", ARCHIVE_NAMESPACE, branch_name, archived_at);
+
+    with_backend(|git| git.update_ref(repo_path, &ref_name, &tip))?;
+    Ok(ref_name)
+}
+
+fn archive_ref(ref_name: &str) -> Result<(&str, u64), String> {
+    ref_name
+        .strip_prefix(ARCHIVE_NAMESPACE)
+        .and_then(|archive| archive.rsplit_once('/'))
+        .and_then(|(branch, time)| Some((branch, time.parse().ok()?)))
+        .ok_or_else(|| format!("Not an archived branch: {}", ref_name))
+}
+
+/// List archived branches, most recently archived first
+#[tauri::command]
+pub async fn list_archived_branches(repo_path: String) -> Result<Vec<ArchivedBranch>, String> {
+    let refs = with_backend(|git| git.list_refs(Path::new(&repo_path), ARCHIVE_NAMESPACE))?;
+
+    let mut archived: Vec<ArchivedBranch> = refs
+        .into_iter()
+        .filter_map(|(ref_name, commit)| {
+            let (branch, archived_at) = archive_ref(&ref_name).ok()?;
+            Some(ArchivedBranch {
+                branch: branch.to_string(),
+                archived_at,
+                commit,
+                ref_name,
+            })
+        })
+        .collect();
+    archived.sort_by_key(|archived| Reverse(archived.archived_at));
+
+    Ok(archived)
+}
+
+/// Recreate an archived branch, under its old name unless `branch_name` is given, and
+/// drop it from the archive. Returns the name of the restored branch.
+#[tauri::command]
+pub async fn restore_archived_branch(
+    repo_path: String,
+    ref_name: String,
+    branch_name: Option<String>,
+) -> Result<String, String> {
+    let repo = Path::new(&repo_path);
+    let (branch, _) = archive_ref(&ref_name)?;
+    let branch = branch_name.unwrap_or_else(|| branch.to_string());
+
+    let tip = with_backend(|git| git.resolve_commit(repo, &ref_name))?;
+    with_backend(|git| git.create_branch(repo, &branch, &tip))?;
+    with_backend(|git| git.delete_ref(repo, &ref_name))?;
+
+    Ok(branch)
+}
+
+/// Drop an archived branch for good
+#[tauri::command]
+pub async fn delete_archived_branch(repo_path: String, ref_name: String) -> Result<(), String> {
+    archive_ref(&ref_name)?;
+    Ok(with_backend(|git| {
+        git.delete_ref(Path::new(&repo_path), &ref_name)
+    })?)
+}
@@ -0,0 +1,150 @@
+use crate::commands::storage;
+use crate::git::with_backend;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// How a carried file is brought into the new worktree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CarryMode {
+    #[default]
+    Copy,
+    /// Share the file's content with the source worktree, so editing one edits both
+    Hardlink,
+    /// Copy-on-write clone where the filesystem supports it (APFS, Btrfs, XFS)
+    Reflink,
+}
+
+/// Untracked or ignored paths matching `pattern` are carried into new worktrees.
+///
+/// Patterns match paths from the worktree root like `.gitignore` entries do: `*`
+/// stops at `/`, so `.env*` only matches at the root and `**/.env.local` at any depth.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CarryRule {
+    pub pattern: String,
+    #[serde(default)]
+    pub mode: CarryMode,
+}
+
+/// Stored in `<git common dir>/codecraft/carry.json`. A path matched by several rules
+/// is carried by the first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CarryConfig {
+    #[serde(default)]
+    pub rules: Vec<CarryRule>,
+}
+
+fn config_file(repo_path: &Path) -> Result<PathBuf, String> {
+    Ok(storage::shared_dir(repo_path)?.join("carry.json"))
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(source)?, target)
+}
+
+#[cfg(windows)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    let link = fs::read_link(source)?;
+    if fs::metadata(source)?.is_dir() {
+        std::os::windows::fs::symlink_dir(link, target)
+    } else {
+        std::os::windows::fs::symlink_file(link, target)
+    }
+}
+
+/// Bring a file, symlink or whole directory over, never replacing anything that
+/// already exists in the target
+fn carry_entry(source: &Path, target: &Path, mode: CarryMode) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(source)?;
+
+    if metadata.is_dir() {
+        fs::create_dir_all(target)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            carry_entry(&entry.path(), &target.join(entry.file_name()), mode)?;
+        }
+        return Ok(());
+    }
+
+    if fs::symlink_metadata(target).is_ok() {
+        return Ok(());
+    }
+    if metadata.file_type().is_symlink() {
+        return copy_symlink(source, target);
+    }
+
+    match mode {
+        CarryMode::Copy => fs::copy(source, target).map(|_| ()),
+        // Hard links can't cross filesystems, so fall back to a copy there
+        CarryMode::Hardlink => {
+            fs::hard_link(source, target).or_else(|_| fs::copy(source, target).map(|_| ()))
+        }
+        CarryMode::Reflink => reflink_copy::reflink_or_copy(source, target).map(|_| ()),
+    }
+}
+
+/// Carry the paths of `source` matching the repository's rules into `worktree`.
+/// Returns the paths carried; a directory is listed once with a trailing `/`. A path
+/// that fails to carry is reported and skipped.
+pub fn carry(source: &Path, worktree: &Path) -> Result<Vec<String>, String> {
+    let config: CarryConfig = storage::read_json(&config_file(source)?)?;
+    let mut carried: Vec<String> = Vec::new();
+
+    for rule in &config.rules {
+        let paths = with_backend(|git| {
+            git.untracked_matching(source, std::slice::from_ref(&rule.pattern))
+        })?;
+
+        for path in paths {
+            let done = carried
+                .iter()
+                .any(|done| *done == path || (done.ends_with('/') && path.starts_with(done)));
+            if done {
+                continue;
+            }
+
+            let relative = path.trim_end_matches('/');
+            let target = worktree.join(relative);
+            let result = target
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| carry_entry(&source.join(relative), &target, rule.mode));
+
+            match result {
+                Ok(()) => carried.push(path),
+                Err(e) => eprintln!(
+                    "Failed to carry {} into {}: {}",
+                    path,
+                    worktree.display(),
+                    e
+                ),
+            }
+        }
+    }
+
+    Ok(carried)
+}
+
+/// Get the rules for carrying untracked files into new worktrees of a repository
+#[tauri::command]
+pub async fn get_carry_config(repo_path: String) -> Result<CarryConfig, String> {
+    storage::read_json(&config_file(Path::new(&repo_path))?)
+}
+
+/// Replace the rules for carrying untracked files into new worktrees of a repository
+#[tauri::command]
+pub async fn set_carry_config(repo_path: String, config: CarryConfig) -> Result<(), String> {
+    storage::write_json(&config_file(Path::new(&repo_path))?, &config)
+}
+
+/// Carry matching untracked files from `source_path` into an existing worktree
+#[tauri::command]
+pub async fn carry_files(
+    source_path: String,
+    worktree_path: String,
+) -> Result<Vec<String>, String> {
+    carry(Path::new(&source_path), Path::new(&worktree_path))
+}
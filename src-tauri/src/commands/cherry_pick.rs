@@ -0,0 +1,50 @@
+use crate::commands::worktree::MergeResult;
+use crate::git::with_backend;
+use std::path::Path;
+
+/// Apply `commits` from any branch, in the order given, onto the branch checked out
+/// in a worktree. `record_origin` appends git's `(cherry picked from commit ...)`
+/// trailer. A conflicting pick is left in progress for `continue_cherry_pick`,
+/// `skip_cherry_pick` or `abort_cherry_pick`.
+#[tauri::command]
+pub async fn cherry_pick(
+    worktree_path: String,
+    commits: Vec<String>,
+    record_origin: Option<bool>,
+) -> Result<MergeResult, String> {
+    if commits.is_empty() {
+        return Err("No commits to cherry-pick".to_string());
+    }
+
+    Ok(with_backend(|git| {
+        git.cherry_pick(
+            Path::new(&worktree_path),
+            &commits,
+            record_origin.unwrap_or(false),
+        )
+    })?)
+}
+
+/// Resume a stopped cherry-pick once its conflicts are resolved and staged
+#[tauri::command]
+pub async fn continue_cherry_pick(worktree_path: String) -> Result<MergeResult, String> {
+    Ok(with_backend(|git| {
+        git.continue_cherry_pick(Path::new(&worktree_path))
+    })?)
+}
+
+/// Drop the commit a cherry-pick stopped on and carry on with the rest
+#[tauri::command]
+pub async fn skip_cherry_pick(worktree_path: String) -> Result<MergeResult, String> {
+    Ok(with_backend(|git| {
+        git.skip_cherry_pick(Path::new(&worktree_path))
+    })?)
+}
+
+/// Abandon a cherry-pick and return the branch to where it was before
+#[tauri::command]
+pub async fn abort_cherry_pick(worktree_path: String) -> Result<(), String> {
+    Ok(with_backend(|git| {
+        git.abort_cherry_pick(Path::new(&worktree_path))
+    })?)
+}
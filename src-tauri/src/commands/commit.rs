@@ -0,0 +1,245 @@
+use crate::commands::diff::{DiffLineKind, DiffTarget, FileDiff};
+use crate::commands::status::FileChange;
+use crate::git::with_backend;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitInfo {
+    pub sha: String,
+    pub parents: Vec<String>,
+    pub summary: String,
+    pub body: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Author date as seconds since the Unix epoch
+    pub author_time: i64,
+    /// Committer date as seconds since the Unix epoch
+    pub commit_time: i64,
+}
+
+/// A changed line, identified by its line numbers as reported by `get_diff`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineRef {
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+}
+
+/// Part of one file's diff: whole hunks by index and/or individual changed lines
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchSelection {
+    pub path: String,
+    #[serde(default)]
+    pub hunks: Vec<usize>,
+    #[serde(default)]
+    pub lines: Vec<LineRef>,
+}
+
+fn format_line(prefix: char, content: &str) -> String {
+    if content.ends_with('\n') {
+        format!("{}{}", prefix, content)
+    } else {
+        format!("{}{}\n\\ No newline at end of file\n", prefix, content)
+    }
+}
+
+/// Build a patch of the selected changes that `git apply --cached` accepts.
+///
+/// Staging applies the patch forwards to the index, so unselected removals stay as
+/// context and unselected additions are dropped. Unstaging applies it in reverse,
+/// so the roles swap: unselected additions stay and unselected removals are dropped.
+fn selection_patch(file: &FileDiff, selection: &PatchSelection, reverse: bool) -> Option<String> {
+    let path = &selection.path;
+    let mut patch = format!("diff --git a/{0} b/{0}\n--- a/{0}\n+++ b/{0}\n", path);
+    let mut shift: i64 = 0;
+    let mut any_selected = false;
+
+    for (index, hunk) in file.hunks.iter().enumerate() {
+        let whole_hunk = selection.hunks.contains(&index);
+        let mut body = String::new();
+        let (mut old_count, mut new_count) = (0u32, 0u32);
+        let mut hunk_selected = false;
+
+        for line in &hunk.lines {
+            let selected = whole_hunk
+                || selection.lines.contains(&LineRef {
+                    old_line: line.old_line,
+                    new_line: line.new_line,
+                });
+            let keep_unselected = match line.kind {
+                DiffLineKind::Removed => !reverse,
+                DiffLineKind::Added => reverse,
+                DiffLineKind::Context => true,
+            };
+
+            if line.kind == DiffLineKind::Context || (!selected && keep_unselected) {
+                body.push_str(&format_line(' ', &line.content));
+                old_count += 1;
+                new_count += 1;
+            } else if selected {
+                hunk_selected = true;
+                if line.kind == DiffLineKind::Removed {
+                    body.push_str(&format_line('-', &line.content));
+                    old_count += 1;
+                } else {
+                    body.push_str(&format_line('+', &line.content));
+                    new_count += 1;
+                }
+            }
+        }
+
+        if !hunk_selected {
+            continue;
+        }
+        any_selected = true;
+
+        // Line numbers must match the side the patch is applied to
+        let (old_start, new_start) = if reverse {
+            (
+                (hunk.new_start as i64 - shift).max(0),
+                hunk.new_start as i64,
+            )
+        } else {
+            (
+                hunk.old_start as i64,
+                (hunk.old_start as i64 + shift).max(0),
+            )
+        };
+        shift += new_count as i64 - old_count as i64;
+
+        patch.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n{}",
+            old_start, old_count, new_start, new_count, body
+        ));
+    }
+
+    any_selected.then_some(patch)
+}
+
+/// Whether `selection` picks every changed line of `file`
+fn selects_everything(file: &FileDiff, selection: &PatchSelection) -> bool {
+    file.hunks.iter().enumerate().all(|(index, hunk)| {
+        selection.hunks.contains(&index)
+            || hunk
+                .lines
+                .iter()
+                .filter(|line| line.kind != DiffLineKind::Context)
+                .all(|line| {
+                    selection.lines.contains(&LineRef {
+                        old_line: line.old_line,
+                        new_line: line.new_line,
+                    })
+                })
+    })
+}
+
+/// Stage whole files, including deletions and untracked files
+#[tauri::command]
+pub async fn stage_files(worktree_path: String, paths: Vec<String>) -> Result<(), String> {
+    Ok(with_backend(|git| {
+        git.stage(Path::new(&worktree_path), &paths)
+    })?)
+}
+
+/// Remove whole files from the index, keeping the working tree as it is
+#[tauri::command]
+pub async fn unstage_files(worktree_path: String, paths: Vec<String>) -> Result<(), String> {
+    Ok(with_backend(|git| {
+        git.unstage(Path::new(&worktree_path), &paths)
+    })?)
+}
+
+/// Stage selected hunks or lines of one file's unstaged changes
+#[tauri::command]
+pub async fn stage_lines(worktree_path: String, selection: PatchSelection) -> Result<(), String> {
+    let worktree = Path::new(&worktree_path);
+    let paths = [selection.path.clone()];
+
+    let status = with_backend(|git| git.status(worktree, false))?;
+    if status
+        .iter()
+        .any(|entry| entry.untracked && entry.path == selection.path)
+    {
+        // Give the file an empty index entry so its lines can be added piecemeal
+        with_backend(|git| git.intent_to_add(worktree, &paths))?;
+    }
+
+    apply_selection(worktree, &selection, DiffTarget::WorkingTree, false)
+}
+
+/// Unstage selected hunks or lines of one file's staged changes
+#[tauri::command]
+pub async fn unstage_lines(worktree_path: String, selection: PatchSelection) -> Result<(), String> {
+    apply_selection(
+        Path::new(&worktree_path),
+        &selection,
+        DiffTarget::Staged,
+        true,
+    )
+}
+
+fn apply_selection(
+    worktree: &Path,
+    selection: &PatchSelection,
+    target: DiffTarget,
+    reverse: bool,
+) -> Result<(), String> {
+    let paths = [selection.path.clone()];
+    let diffs = with_backend(|git| git.diff(worktree, &target, &paths, false))?;
+    let file = diffs
+        .iter()
+        .find(|file| file.hunks.iter().any(|hunk| !hunk.lines.is_empty()))
+        .ok_or_else(|| format!("No changes to select in {}", selection.path))?;
+
+    // A patch of every line would only empty the file, so staging a whole deletion or
+    // unstaging a whole addition goes through the index as for whole files
+    let whole_file = matches!(
+        (file.change, reverse),
+        (FileChange::Deleted, false) | (FileChange::Added, true)
+    ) && selects_everything(file, selection);
+    if whole_file {
+        return Ok(with_backend(|git| {
+            if reverse {
+                git.unstage(worktree, &paths)
+            } else {
+                git.stage(worktree, &paths)
+            }
+        })?);
+    }
+
+    let patch = selection_patch(file, selection, reverse)
+        .ok_or_else(|| format!("No changed lines selected in {}", selection.path))?;
+
+    Ok(with_backend(|git| {
+        git.apply_to_index(worktree, &patch, reverse)
+    })?)
+}
+
+/// Commit the staged changes. With `amend`, replace HEAD instead, keeping its message
+/// when none is given; `sign_off` adds a `Signed-off-by` trailer.
+#[tauri::command]
+pub async fn commit_changes(
+    worktree_path: String,
+    message: Option<String>,
+    amend: Option<bool>,
+    sign_off: Option<bool>,
+) -> Result<CommitInfo, String> {
+    let worktree = Path::new(&worktree_path);
+    let amend = amend.unwrap_or(false);
+    let message = message.filter(|message| !message.trim().is_empty());
+
+    if message.is_none() && !amend {
+        return Err("A commit message is required".to_string());
+    }
+
+    with_backend(|git| {
+        git.commit(
+            worktree,
+            message.as_deref(),
+            amend,
+            sign_off.unwrap_or(false),
+        )
+    })?;
+
+    Ok(with_backend(|git| git.commit_info(worktree, "HEAD"))?)
+}
@@ -0,0 +1,176 @@
+use crate::commands::status::ConflictKind;
+use crate::commands::worktree::MergeResult;
+use crate::git::with_backend;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Which version of a conflicted file to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictSide {
+    Ours,
+    Theirs,
+}
+
+impl ConflictSide {
+    /// Index stage holding this side of an unmerged file
+    fn stage(self) -> u8 {
+        match self {
+            ConflictSide::Ours => 2,
+            ConflictSide::Theirs => 3,
+        }
+    }
+}
+
+/// An unmerged file with every version needed to resolve it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictFile {
+    pub path: String,
+    pub kind: ConflictKind,
+    /// Whether any version is binary, in which case no content is returned
+    pub is_binary: bool,
+    /// Content at the merge base and on each side; `None` where the file doesn't exist
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+    /// Current working tree content, usually with conflict markers
+    pub working: Option<String>,
+}
+
+fn is_binary(content: &[u8]) -> bool {
+    content.contains(&0)
+}
+
+fn read_conflict(worktree: &Path, path: &str, kind: ConflictKind) -> Result<ConflictFile, String> {
+    let base = with_backend(|git| git.conflict_stage(worktree, path, 1))?;
+    let ours = with_backend(|git| git.conflict_stage(worktree, path, 2))?;
+    let theirs = with_backend(|git| git.conflict_stage(worktree, path, 3))?;
+    let working = fs::read(worktree.join(path)).ok();
+
+    let versions = [base, ours, theirs, working];
+    let binary = versions.iter().flatten().any(|content| is_binary(content));
+    let [base, ours, theirs, working] = versions.map(|content| {
+        content
+            .filter(|_| !binary)
+            .map(|content| String::from_utf8_lossy(&content).to_string())
+    });
+
+    Ok(ConflictFile {
+        path: path.to_string(),
+        kind,
+        is_binary: binary,
+        base,
+        ours,
+        theirs,
+        working,
+    })
+}
+
+/// List the unmerged files of a worktree with their base, ours and theirs content
+#[tauri::command]
+pub async fn get_conflicts(worktree_path: String) -> Result<Vec<ConflictFile>, String> {
+    let worktree = Path::new(&worktree_path);
+    let status = with_backend(|git| git.status(worktree, false))?;
+
+    status
+        .into_iter()
+        .filter_map(|entry| entry.conflict.map(|kind| (entry.path, kind)))
+        .map(|(path, kind)| read_conflict(worktree, &path, kind))
+        .collect()
+}
+
+/// Resolve a file by taking one side wholesale. If that side deleted the file, the
+/// deletion is what gets staged.
+#[tauri::command]
+pub async fn accept_conflict_side(
+    worktree_path: String,
+    path: String,
+    side: ConflictSide,
+) -> Result<(), String> {
+    let worktree = Path::new(&worktree_path);
+    let file = worktree.join(&path);
+
+    match with_backend(|git| git.conflict_stage(worktree, &path, side.stage()))? {
+        Some(content) => fs::write(&file, content)
+            .map_err(|e| format!("Failed to write {}: {}", file.display(), e))?,
+        None if file.exists() => fs::remove_file(&file)
+            .map_err(|e| format!("Failed to remove {}: {}", file.display(), e))?,
+        None => {}
+    }
+
+    Ok(with_backend(|git| {
+        git.stage(worktree, std::slice::from_ref(&path))
+    })?)
+}
+
+/// Write the resolved content of a file and mark it resolved
+#[tauri::command]
+pub async fn save_conflict_resolution(
+    worktree_path: String,
+    path: String,
+    content: String,
+) -> Result<(), String> {
+    let worktree = Path::new(&worktree_path);
+    let file = worktree.join(&path);
+
+    fs::write(&file, content).map_err(|e| format!("Failed to write {}: {}", file.display(), e))?;
+
+    Ok(with_backend(|git| {
+        git.stage(worktree, std::slice::from_ref(&path))
+    })?)
+}
+
+/// Mark files resolved as they are on disk, e.g. after editing them elsewhere
+#[tauri::command]
+pub async fn mark_resolved(worktree_path: String, paths: Vec<String>) -> Result<(), String> {
+    Ok(with_backend(|git| {
+        git.stage(Path::new(&worktree_path), &paths)
+    })?)
+}
+
+/// Commit a merge once every conflict is resolved, using git's prepared message
+/// unless one is given. A stopped rebase or cherry-pick is resumed with
+/// `continue_rebase` or `continue_cherry_pick` instead.
+#[tauri::command]
+pub async fn conclude_merge(
+    worktree_path: String,
+    message: Option<String>,
+) -> Result<MergeResult, String> {
+    let worktree = Path::new(&worktree_path);
+    let status = with_backend(|git| git.status(worktree, false))?;
+    let conflicts: Vec<String> = status
+        .into_iter()
+        .filter(|entry| entry.conflict.is_some())
+        .map(|entry| entry.path)
+        .collect();
+
+    if !conflicts.is_empty() {
+        return Ok(MergeResult {
+            success: false,
+            message: format!("{} file(s) still have conflicts", conflicts.len()),
+            conflicts,
+            worktree_path: Some(worktree_path),
+            commit: None,
+        });
+    }
+
+    let message = message.filter(|message| !message.trim().is_empty());
+    with_backend(|git| git.commit(worktree, message.as_deref(), false, false))?;
+
+    Ok(MergeResult {
+        success: true,
+        message: "Merge successful".to_string(),
+        conflicts: vec![],
+        commit: Some(with_backend(|git| git.resolve_commit(worktree, "HEAD"))?),
+        worktree_path: Some(worktree_path),
+    })
+}
+
+/// Abandon an in-progress merge and restore the pre-merge state
+#[tauri::command]
+pub async fn abort_merge(worktree_path: String) -> Result<(), String> {
+    Ok(with_backend(|git| {
+        git.abort_merge(Path::new(&worktree_path))
+    })?)
+}
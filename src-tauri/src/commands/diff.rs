@@ -0,0 +1,103 @@
+use crate::commands::lineage;
+use crate::commands::status::FileChange;
+use crate::git::with_backend;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The two sides `get_diff` compares
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DiffTarget {
+    /// Index → working tree (unstaged changes, including untracked files)
+    WorkingTree,
+    /// HEAD → index (staged changes)
+    Staged,
+    /// Merge-base with the parent branch → working tree. Defaults to the parent
+    /// recorded in the lineage.
+    Parent { parent: Option<String> },
+    /// One revision → another
+    Refs { from: String, to: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub content: String,
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    /// The `@@ -a,b +c,d @@ ...` line
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub change: FileChange,
+    pub is_binary: bool,
+    /// Blob ids of each side; `None` when the side is absent or only on disk
+    pub old_blob: Option<String>,
+    pub new_blob: Option<String>,
+    /// Full text of each side, when requested and not binary
+    pub old_content: Option<String>,
+    pub new_content: Option<String>,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// Size of the change to one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileStat {
+    pub path: String,
+    /// Where the file was before, when it was renamed
+    pub old_path: Option<String>,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// Binary files have no line counts
+    pub is_binary: bool,
+}
+
+/// Diff a worktree against the chosen base, optionally limited to `paths`. Set
+/// `include_content` to also get the full old and new text of each file, e.g. for a
+/// side-by-side editor.
+#[tauri::command]
+pub async fn get_diff(
+    worktree_path: String,
+    target: DiffTarget,
+    paths: Option<Vec<String>>,
+    include_content: Option<bool>,
+) -> Result<Vec<FileDiff>, String> {
+    let worktree = Path::new(&worktree_path);
+
+    let target = match target {
+        DiffTarget::Parent { parent: None } => DiffTarget::Parent {
+            parent: Some(lineage::worktree_parent(worktree)?),
+        },
+        target => target,
+    };
+
+    Ok(with_backend(|git| {
+        git.diff(
+            worktree,
+            &target,
+            paths.as_deref().unwrap_or_default(),
+            include_content.unwrap_or(false),
+        )
+    })?)
+}
@@ -0,0 +1,120 @@
+use crate::commands::lineage;
+use crate::git::with_backend;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Pairs kept before the cache is emptied and starts over
+const MAX_CACHED: usize = 4096;
+
+// Divergence by (commit, parent commit). Commits never change, so an entry stays
+// right forever; when a ref moves, the new pair simply isn't cached yet.
+lazy_static::lazy_static! {
+    static ref CACHE: Mutex<HashMap<(String, String), Divergence>> = Mutex::new(HashMap::new());
+}
+
+/// How far one commit has moved away from another
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Divergence {
+    /// Commits only on the first side
+    pub ahead: usize,
+    /// Commits only on the second side
+    pub behind: usize,
+    /// `None` when the histories are unrelated
+    pub merge_base: Option<String>,
+}
+
+/// A graph edge with how far the worktree has moved from its parent branch
+#[derive(Debug, Clone, Serialize)]
+pub struct EdgeDivergence {
+    pub worktree_path: String,
+    pub parent_branch: String,
+    /// Worktree at the other end of the edge in the graph
+    pub parent_path: Option<String>,
+    #[serde(flatten)]
+    pub divergence: Divergence,
+}
+
+/// Ahead/behind counts and merge base of every worktree against its parent branch,
+/// which is the recorded parent or else the main worktree's branch. Only pairs of
+/// commits not seen before reach git, in one batch.
+#[tauri::command]
+pub async fn get_ahead_behind_matrix(repo_path: String) -> Result<Vec<EdgeDivergence>, String> {
+    let repo = Path::new(&repo_path);
+    let worktrees = with_backend(|git| git.list_worktrees(repo))?;
+    let main_branch = worktrees
+        .iter()
+        .find(|wt| wt.is_main)
+        .and_then(|wt| wt.branch.clone());
+    let linked = lineage::link(worktrees, &lineage::load(repo)?);
+
+    // Edges with the (head, parent tip) pair each one compares; parents are resolved
+    // once however many children they have
+    let mut parent_tips: HashMap<String, Option<String>> = HashMap::new();
+    let mut edges = Vec::new();
+    for node in linked {
+        // The main worktree has no parent and an unborn branch has nothing to compare
+        if node.worktree.is_main || node.worktree.head.trim_start_matches('0').is_empty() {
+            continue;
+        }
+        let Some(parent_branch) = node.parent_branch.or_else(|| main_branch.clone()) else {
+            continue;
+        };
+        if node.worktree.branch.as_ref() == Some(&parent_branch) {
+            continue;
+        }
+
+        let tip = parent_tips
+            .entry(parent_branch.clone())
+            .or_insert_with(|| {
+                let reference = format!("refs/heads/{}", parent_branch);
+                with_backend(|git| git.resolve_commit(repo, &reference)).ok()
+            })
+            .clone();
+        if let Some(tip) = tip {
+            let pair = (node.worktree.head, tip);
+            edges.push((node.worktree.path, parent_branch, node.parent_path, pair));
+        }
+    }
+
+    let mut known: HashMap<(String, String), Divergence> = HashMap::new();
+    let mut missing = Vec::new();
+    {
+        let cache = CACHE.lock();
+        for (_, _, _, pair) in &edges {
+            match cache.get(pair) {
+                Some(divergence) => {
+                    known.insert(pair.clone(), divergence.clone());
+                }
+                None => missing.push(pair.clone()),
+            }
+        }
+    }
+
+    if !missing.is_empty() {
+        missing.sort();
+        missing.dedup();
+        let computed = with_backend(|git| git.divergence(repo, &missing))?;
+        let computed: Vec<_> = missing.into_iter().zip(computed).collect();
+
+        let mut cache = CACHE.lock();
+        if cache.len() + computed.len() > MAX_CACHED {
+            cache.clear();
+        }
+        cache.extend(computed.iter().cloned());
+        known.extend(computed);
+    }
+
+    Ok(edges
+        .into_iter()
+        .filter_map(|(worktree_path, parent_branch, parent_path, pair)| {
+            Some(EdgeDivergence {
+                divergence: known.get(&pair)?.clone(),
+                worktree_path,
+                parent_branch,
+                parent_path,
+            })
+        })
+        .collect())
+}
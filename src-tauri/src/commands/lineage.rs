@@ -0,0 +1,201 @@
+use crate::commands::storage;
+use crate::commands::worktree::Worktree;
+use crate::git::with_backend;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+// Serialize read-modify-write cycles on the lineage file
+lazy_static::lazy_static! {
+    static ref LINEAGE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Branch → parent branch relationships for one repository.
+///
+/// Stored as JSON in `<git common dir>/codecraft/lineage.json` so every worktree
+/// (and every machine the repository is copied to) sees the same graph.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Lineage {
+    #[serde(default)]
+    pub parents: BTreeMap<String, String>,
+}
+
+impl Lineage {
+    /// Follow a branch rename, both as a child and as anyone's parent
+    pub fn rename(&mut self, old_name: &str, new_name: &str) {
+        if let Some(parent) = self.parents.remove(old_name) {
+            self.parents.insert(new_name.to_string(), parent);
+        }
+        for parent in self
+            .parents
+            .values_mut()
+            .filter(|parent| *parent == old_name)
+        {
+            *parent = new_name.to_string();
+        }
+    }
+
+    /// Whether `ancestor` appears on the recorded parent chain of `branch`
+    pub fn is_ancestor(&self, ancestor: &str, branch: &str) -> bool {
+        let mut seen = BTreeSet::new();
+        let mut current = branch;
+
+        while let Some(parent) = self.parents.get(current) {
+            if parent == ancestor {
+                return true;
+            }
+            if !seen.insert(parent) {
+                break;
+            }
+            current = parent;
+        }
+
+        false
+    }
+}
+
+/// A worktree together with the worktree it was forked from
+#[derive(Debug, Serialize)]
+pub struct LinkedWorktree {
+    #[serde(flatten)]
+    pub worktree: Worktree,
+    /// Parent branch as recorded in the lineage, if any
+    pub parent_branch: Option<String>,
+    /// Path of the worktree the node hangs off in the graph
+    pub parent_path: Option<String>,
+}
+
+fn lineage_file(repo_path: &Path) -> Result<PathBuf, String> {
+    Ok(storage::shared_dir(repo_path)?.join("lineage.json"))
+}
+
+/// Load the stored lineage for the repository containing `repo_path`
+pub fn load(repo_path: &Path) -> Result<Lineage, String> {
+    storage::read_json(&lineage_file(repo_path)?)
+}
+
+/// Apply `change` to the stored lineage and persist the result
+pub fn update<T>(repo_path: &Path, change: impl FnOnce(&mut Lineage) -> T) -> Result<T, String> {
+    let file = lineage_file(repo_path)?;
+    let _guard = LINEAGE_LOCK.lock();
+
+    let mut lineage: Lineage = storage::read_json(&file)?;
+    let result = change(&mut lineage);
+    storage::write_json(&file, &lineage)?;
+
+    Ok(result)
+}
+
+/// Recorded parent of `branch_name`, falling back to the branch checked out in the
+/// main worktree the way the graph does
+pub fn resolve_parent(repo_path: &Path, branch_name: &str) -> Result<Option<String>, String> {
+    if let Some(parent) = load(repo_path)?.parents.remove(branch_name) {
+        return Ok(Some(parent));
+    }
+
+    let worktrees = with_backend(|git| git.list_worktrees(repo_path))?;
+    Ok(worktrees
+        .into_iter()
+        .find(|wt| wt.is_main)
+        .and_then(|wt| wt.branch)
+        .filter(|main_branch| main_branch != branch_name))
+}
+
+/// Parent branch of whatever branch is checked out in `worktree_path`
+pub fn worktree_parent(worktree_path: &Path) -> Result<String, String> {
+    let worktrees = with_backend(|git| git.list_worktrees(worktree_path))?;
+    let branch = worktrees
+        .into_iter()
+        .find(|wt| Path::new(&wt.path) == worktree_path)
+        .and_then(|wt| wt.branch)
+        .ok_or_else(|| format!("No branch is checked out in {}", worktree_path.display()))?;
+
+    resolve_parent(worktree_path, &branch)?
+        .ok_or_else(|| format!("No parent branch known for {}", branch))
+}
+
+/// Attach each worktree to its parent: the worktree that has the recorded parent
+/// branch checked out, or the main worktree when there is none
+pub fn link(worktrees: Vec<Worktree>, lineage: &Lineage) -> Vec<LinkedWorktree> {
+    let main_path = worktrees
+        .iter()
+        .find(|wt| wt.is_main)
+        .map(|wt| wt.path.clone());
+
+    let branch_paths: BTreeMap<String, String> = worktrees
+        .iter()
+        .filter_map(|wt| wt.branch.clone().map(|branch| (branch, wt.path.clone())))
+        .collect();
+
+    worktrees
+        .into_iter()
+        .map(|worktree| {
+            let parent_branch = worktree
+                .branch
+                .as_ref()
+                .and_then(|branch| lineage.parents.get(branch))
+                .cloned();
+
+            let parent_path = if worktree.is_main {
+                None
+            } else {
+                parent_branch
+                    .as_ref()
+                    .and_then(|parent| branch_paths.get(parent))
+                    .filter(|path| **path != worktree.path)
+                    .cloned()
+                    .or_else(|| main_path.clone())
+            };
+
+            LinkedWorktree {
+                worktree,
+                parent_branch,
+                parent_path,
+            }
+        })
+        .collect()
+}
+
+/// List all worktrees linked to their parents according to the stored lineage
+#[tauri::command]
+pub async fn get_worktree_graph(repo_path: String) -> Result<Vec<LinkedWorktree>, String> {
+    let repo = Path::new(&repo_path);
+    let worktrees = with_backend(|git| git.list_worktrees(repo))?;
+    let lineage = load(repo)?;
+
+    Ok(link(worktrees, &lineage))
+}
+
+/// Record (or clear, with `None`) the parent branch of a branch
+#[tauri::command]
+pub async fn set_worktree_parent(
+    repo_path: String,
+    branch_name: String,
+    parent_branch: Option<String>,
+) -> Result<(), String> {
+    if parent_branch.as_deref() == Some(branch_name.as_str()) {
+        return Err(format!(
+            "A branch cannot be its own parent: {}",
+            branch_name
+        ));
+    }
+
+    update(Path::new(&repo_path), |lineage| {
+        match parent_branch {
+            Some(parent) => {
+                if lineage.is_ancestor(&branch_name, &parent) {
+                    return Err(format!(
+                        "Cannot make {} the parent of {}: it descends from it",
+                        parent, branch_name
+                    ));
+                }
+                lineage.parents.insert(branch_name, parent);
+            }
+            None => {
+                lineage.parents.remove(&branch_name);
+            }
+        }
+        Ok(())
+    })?
+}
@@ -0,0 +1,258 @@
+use crate::commands::commit::CommitInfo;
+use crate::git::with_backend;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const DEFAULT_PAGE_SIZE: usize = 200;
+
+/// A graph line from column `from` in one row to column `to` in the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+}
+
+/// One row of the commit graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    #[serde(flatten)]
+    pub commit: CommitInfo,
+    /// Branches, remote branches and tags pointing at the commit
+    pub refs: Vec<String>,
+    /// Column of the commit's node
+    pub lane: usize,
+    /// Lines from this row down to the next
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Assign each commit a column and the lines leading to its parents.
+///
+/// `lanes[i]` holds the commit column `i` is waiting for. A commit takes the first
+/// column waiting for it (or the first free one) and its first parent inherits that
+/// column, taking it over from any column further right already waiting for it.
+/// Further parents get a column of their own unless one already waits for them.
+fn layout(commits: &[CommitInfo]) -> Vec<(usize, Vec<GraphEdge>)> {
+    let mut lanes: Vec<Option<&str>> = Vec::new();
+    let mut rows: Vec<(usize, Vec<GraphEdge>)> = Vec::with_capacity(commits.len());
+
+    let free_lane = |lanes: &mut Vec<Option<&str>>| match lanes.iter().position(Option::is_none) {
+        Some(index) => index,
+        None => {
+            lanes.push(None);
+            lanes.len() - 1
+        }
+    };
+
+    for commit in commits {
+        let sha = commit.sha.as_str();
+        let lane = match lanes.iter().position(|waiting| *waiting == Some(sha)) {
+            Some(index) => index,
+            None => free_lane(&mut lanes),
+        };
+
+        // Other columns waiting for this commit end here; bend their last line into it
+        for (index, waiting) in lanes.iter_mut().enumerate() {
+            if index != lane && *waiting == Some(sha) {
+                *waiting = None;
+                if let Some((_, edges)) = rows.last_mut() {
+                    for edge in edges.iter_mut().filter(|edge| edge.to == index) {
+                        edge.to = lane;
+                    }
+                }
+            }
+        }
+        lanes[lane] = None;
+
+        let mut edges: Vec<GraphEdge> = lanes
+            .iter()
+            .enumerate()
+            .filter(|(_, waiting)| waiting.is_some())
+            .map(|(index, _)| GraphEdge {
+                from: index,
+                to: index,
+            })
+            .collect();
+
+        for (index, parent) in commit.parents.iter().enumerate() {
+            let parent = parent.as_str();
+            let target = match lanes.iter().position(|waiting| *waiting == Some(parent)) {
+                // Pull a first parent further right into this column to keep lines straight
+                Some(existing) if index == 0 && existing > lane => {
+                    lanes[existing] = None;
+                    for edge in edges.iter_mut().filter(|edge| edge.from == existing) {
+                        edge.to = lane;
+                    }
+                    lane
+                }
+                Some(existing) => existing,
+                None if index == 0 => lane,
+                None => free_lane(&mut lanes),
+            };
+            lanes[target] = Some(parent);
+            edges.push(GraphEdge {
+                from: lane,
+                to: target,
+            });
+        }
+
+        while lanes.last() == Some(&None) {
+            lanes.pop();
+        }
+
+        rows.push((lane, edges));
+    }
+
+    rows
+}
+
+/// Page through the history of `revisions` (default `HEAD`), newest first, with the
+/// graph laid out. Lanes depend on every newer commit, so each page is laid out from
+/// the top of the history.
+#[tauri::command]
+pub async fn get_commit_log(
+    worktree_path: String,
+    revisions: Option<Vec<String>>,
+    skip: Option<usize>,
+    limit: Option<usize>,
+) -> Result<Vec<LogEntry>, String> {
+    let repo = Path::new(&worktree_path);
+    let revisions = revisions.unwrap_or_else(|| vec!["HEAD".to_string()]);
+    let skip = skip.unwrap_or(0);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+
+    // One extra commit settles where the last row's lines end
+    let commits = with_backend(|git| git.log(repo, &revisions, skip + limit + 1))?;
+    let mut refs = with_backend(|git| git.commit_refs(repo))?;
+    let rows = layout(&commits);
+
+    Ok(commits
+        .into_iter()
+        .zip(rows)
+        .skip(skip)
+        .take(limit)
+        .map(|(commit, (lane, edges))| LogEntry {
+            refs: refs.remove(&commit.sha).unwrap_or_default(),
+            commit,
+            lane,
+            edges,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(sha: &str, parents: &[&str]) -> CommitInfo {
+        CommitInfo {
+            sha: sha.to_string(),
+            parents: parents.iter().map(|parent| parent.to_string()).collect(),
+            summary: String::new(),
+            body: String::new(),
+            author_name: String::new(),
+            author_email: String::new(),
+            author_time: 0,
+            commit_time: 0,
+        }
+    }
+
+    /// Each row's lane and its edges as `(from, to)`
+    fn laid_out(commits: &[CommitInfo]) -> Vec<(usize, Vec<(usize, usize)>)> {
+        layout(commits)
+            .into_iter()
+            .map(|(lane, edges)| (lane, edges.iter().map(|e| (e.from, e.to)).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn linear_history_stays_in_one_lane() {
+        let commits = [commit("c", &["b"]), commit("b", &["a"]), commit("a", &[])];
+        assert_eq!(
+            laid_out(&commits),
+            [(0, vec![(0, 0)]), (0, vec![(0, 0)]), (0, vec![])]
+        );
+    }
+
+    #[test]
+    fn merge_opens_a_lane_for_the_second_parent() {
+        let commits = [
+            commit("merge", &["main", "side"]),
+            commit("main", &["base"]),
+            commit("side", &["base"]),
+            commit("base", &[]),
+        ];
+        assert_eq!(
+            laid_out(&commits),
+            [
+                (0, vec![(0, 0), (0, 1)]),
+                (0, vec![(1, 1), (0, 0)]),
+                // The side lane joins the first one waiting for the same parent
+                (1, vec![(0, 0), (1, 0)]),
+                (0, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn octopus_merge_opens_a_lane_per_extra_parent() {
+        let commits = [
+            commit("octopus", &["a", "b", "c"]),
+            commit("a", &["base"]),
+            commit("b", &["base"]),
+            commit("c", &["base"]),
+            commit("base", &[]),
+        ];
+        assert_eq!(
+            laid_out(&commits),
+            [
+                (0, vec![(0, 0), (0, 1), (0, 2)]),
+                (0, vec![(1, 1), (2, 2), (0, 0)]),
+                (1, vec![(0, 0), (2, 2), (1, 0)]),
+                (2, vec![(0, 0), (2, 0)]),
+                (0, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn lane_of_an_ended_branch_is_reused() {
+        let commits = [
+            commit("tip", &["base"]),
+            commit("other", &["root"]),
+            // An unrelated root ends its lane
+            commit("root", &[]),
+            // The next tip takes that lane instead of opening a third
+            commit("later", &["base"]),
+            commit("base", &[]),
+        ];
+        assert_eq!(
+            laid_out(&commits),
+            [
+                (0, vec![(0, 0)]),
+                (1, vec![(0, 0), (1, 1)]),
+                (1, vec![(0, 0)]),
+                (1, vec![(0, 0), (1, 0)]),
+                (0, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn first_parent_pulls_a_waiting_lane_into_its_own() {
+        // `side` is first waited for as the merge's second parent, then as the first
+        // parent of `topic`, which takes it over into its own column
+        let commits = [
+            commit("merge", &["topic", "side"]),
+            commit("topic", &["side"]),
+            commit("side", &[]),
+        ];
+        assert_eq!(
+            laid_out(&commits),
+            [
+                (0, vec![(0, 0), (0, 1)]),
+                (0, vec![(1, 0), (0, 0)]),
+                (0, vec![]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use crate::commands::diff::FileStat;
+use crate::commands::status::ConflictKind;
+use crate::git::with_backend;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// One conflicted region of a file, as the markers in the merge result show it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictHunk {
+    /// Line of the `<<<<<<<` marker in the merged file, from 1
+    pub start_line: usize,
+    pub ours: String,
+    /// Only there when `merge.conflictStyle` is `diff3` or `zdiff3`
+    pub base: Option<String>,
+    pub theirs: String,
+}
+
+/// A file the merge would leave unmerged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PredictedConflict {
+    pub path: String,
+    pub kind: ConflictKind,
+    pub is_binary: bool,
+    /// Empty when the conflict isn't about content, e.g. a file modified on one side
+    /// and deleted on the other
+    pub hunks: Vec<ConflictHunk>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MergePreview {
+    pub source_branch: String,
+    pub target_branch: String,
+    /// The target already contains the source, so there is nothing to merge
+    pub up_to_date: bool,
+    /// The target has nothing of its own and would just move to the source
+    pub fast_forward: bool,
+    pub clean: bool,
+    /// `None` when the histories are unrelated
+    pub merge_base: Option<String>,
+    pub conflicts: Vec<PredictedConflict>,
+    /// What git reported, e.g. `CONFLICT (content): Merge conflict in src/main.rs`
+    pub messages: Vec<String>,
+    /// What the merge would change on the target, conflict markers included
+    pub files: Vec<FileStat>,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Ours,
+    Base,
+    Theirs,
+}
+
+/// Split merged content into the regions between its conflict markers
+fn conflict_hunks(content: &str) -> Vec<ConflictHunk> {
+    let mut hunks = Vec::new();
+    let mut open: Option<(Section, ConflictHunk)> = None;
+
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let marker = line.trim_end_matches(['\n', '\r']);
+
+        let Some((section, hunk)) = open.as_mut() else {
+            if marker.starts_with("<<<<<<<") {
+                let hunk = ConflictHunk {
+                    start_line: index + 1,
+                    ours: String::new(),
+                    base: None,
+                    theirs: String::new(),
+                };
+                open = Some((Section::Ours, hunk));
+            }
+            continue;
+        };
+
+        match *section {
+            Section::Ours if marker.starts_with("|||||||") => {
+                *section = Section::Base;
+                hunk.base = Some(String::new());
+            }
+            Section::Ours | Section::Base if marker == "=======" => *section = Section::Theirs,
+            Section::Theirs if marker.starts_with(">>>>>>>") => {
+                hunks.extend(open.take().map(|(_, hunk)| hunk));
+            }
+            Section::Ours => hunk.ours.push_str(line),
+            Section::Base => hunk.base.get_or_insert_with(String::new).push_str(line),
+            Section::Theirs => hunk.theirs.push_str(line),
+        }
+    }
+
+    hunks
+}
+
+/// Work out what merging `source_branch` into `target_branch` would do, without
+/// checking anything out: whether it conflicts, where, and what it changes. Only
+/// objects are written; no worktree, index or ref is touched.
+#[tauri::command]
+pub async fn preview_merge(
+    repo_path: String,
+    source_branch: String,
+    target_branch: String,
+) -> Result<MergePreview, String> {
+    let repo = Path::new(&repo_path);
+    let pair = [(target_branch.clone(), source_branch.clone())];
+    let divergence = with_backend(|git| git.divergence(repo, &pair))?
+        .pop()
+        .ok_or("Failed to compare the branches")?;
+
+    let merged = with_backend(|git| git.merge_tree(repo, &target_branch, &source_branch))?;
+
+    let mut conflicts = Vec::new();
+    for (path, kind) in merged.conflicts {
+        let content = with_backend(|git| git.blob_at(repo, &merged.tree, &path))?;
+        let is_binary = content.as_ref().is_some_and(|content| content.contains(&0));
+        let hunks = match content.filter(|_| !is_binary) {
+            Some(content) => conflict_hunks(&String::from_utf8_lossy(&content)),
+            None => vec![],
+        };
+
+        conflicts.push(PredictedConflict {
+            path,
+            kind,
+            is_binary,
+            hunks,
+        });
+    }
+
+    let files = with_backend(|git| git.diff_stat(repo, &target_branch, &merged.tree))?;
+
+    Ok(MergePreview {
+        up_to_date: divergence.behind == 0,
+        fast_forward: divergence.ahead == 0 && divergence.behind > 0,
+        clean: conflicts.is_empty(),
+        merge_base: divergence.merge_base,
+        conflicts,
+        messages: merged.messages,
+        insertions: files.iter().map(|file| file.insertions).sum(),
+        deletions: files.iter().map(|file| file.deletions).sum(),
+        files,
+        source_branch,
+        target_branch,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each hunk as `(start_line, ours, base, theirs)`
+    fn hunks(content: &str) -> Vec<(usize, String, Option<String>, String)> {
+        conflict_hunks(content)
+            .into_iter()
+            .map(|hunk| (hunk.start_line, hunk.ours, hunk.base, hunk.theirs))
+            .collect()
+    }
+
+    #[test]
+    fn reads_merge_style_hunks() {
+        // f.txt of `git merge-tree --write-tree ours theirs`, plus a second hunk
+        let content = concat!(
+            "a\n",
+            "<<<<<<< ours\n",
+            "ours\n",
+            "=======\n",
+            "theirs\n",
+            ">>>>>>> theirs\n",
+            "c\n",
+            "<<<<<<< ours\n",
+            "=======\n",
+            "added\n",
+            "twice\n",
+            ">>>>>>> theirs\n",
+        );
+
+        assert_eq!(
+            hunks(content),
+            [
+                (2, "ours\n".to_string(), None, "theirs\n".to_string()),
+                (8, String::new(), None, "added\ntwice\n".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_diff3_style_hunks() {
+        // The same merge with `merge.conflictStyle=diff3`, with CRLF line endings
+        let content = concat!(
+            "a\r\n",
+            "<<<<<<< ours\r\n",
+            "ours\r\n",
+            "||||||| 7f23062\r\n",
+            "b\r\n",
+            "=======\r\n",
+            "theirs\r\n",
+            ">>>>>>> theirs\r\n",
+            "c\r\n",
+        );
+
+        assert_eq!(
+            hunks(content),
+            [(
+                2,
+                "ours\r\n".to_string(),
+                Some("b\r\n".to_string()),
+                "theirs\r\n".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn ignores_clean_content_and_unterminated_hunks() {
+        assert!(hunks("a\nb\n====== not a marker\n").is_empty());
+        assert!(hunks("a\n<<<<<<< ours\nours\n=======\ntheirs\n").is_empty());
+    }
+}
@@ -1,3 +1,22 @@
+pub mod askpass;
+pub mod branch;
+pub mod carry;
+pub mod cherry_pick;
+pub mod commit;
+pub mod conflict;
+pub mod diff;
+pub mod divergence;
 pub mod filesystem;
+pub mod lineage;
+pub mod log;
+pub mod merge_preview;
+pub mod provision;
 pub mod pty;
+pub mod rebase;
+pub mod remote;
+pub mod restack;
+pub mod stash;
+pub mod status;
+pub mod storage;
+pub mod watcher;
 pub mod worktree;
@@ -0,0 +1,495 @@
+use crate::commands::storage;
+use crate::git::with_backend;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+const PROVISION_EVENT: &str = "provision-event";
+
+/// How long a finished step's output may keep arriving. Anything the step left running
+/// in the background (or that escaped a cancel) can hold the pipes open indefinitely.
+const OUTPUT_GRACE: Duration = Duration::from_secs(2);
+
+/// How often a reader waiting for output checks whether it should stop
+const READ_POLL: Duration = Duration::from_millis(50);
+
+// Cancel flags of the provisioning runs in progress, by canonical worktree path
+lazy_static::lazy_static! {
+    static ref RUNS: Mutex<HashMap<String, Arc<AtomicBool>>> = Mutex::new(HashMap::new());
+}
+
+/// One shell command of a recipe, run from the root of the new worktree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvisionStep {
+    /// Label shown instead of the command
+    #[serde(default)]
+    pub name: Option<String>,
+    pub run: String,
+}
+
+/// Commands run in every new worktree of a repository, in order, stopping at the
+/// first failure.
+///
+/// Stored in `<git common dir>/codecraft/provision.json`. Steps see the new
+/// worktree in `CODECRAFT_WORKTREE` and the main one in `CODECRAFT_MAIN_WORKTREE`,
+/// so copying `.env` is `cp "$CODECRAFT_MAIN_WORKTREE/.env" .`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProvisionRecipe {
+    #[serde(default)]
+    pub steps: Vec<ProvisionStep>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvisionState {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+/// Outcome of the last provisioning run of a worktree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvisionRecord {
+    pub state: ProvisionState,
+    /// Seconds since the Unix epoch
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    /// Step that failed or was cancelled
+    pub step: Option<usize>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProvisionUpdate {
+    StepStarted {
+        step: usize,
+        name: String,
+    },
+    Output {
+        step: usize,
+        stream: OutputStream,
+        data: String,
+    },
+    Finished {
+        record: ProvisionRecord,
+    },
+}
+
+/// Payload of the `provision-event` event
+#[derive(Debug, Clone, Serialize)]
+pub struct ProvisionEvent {
+    pub worktree_path: String,
+    #[serde(flatten)]
+    pub update: ProvisionUpdate,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn recipe_file(repo_path: &Path) -> Result<PathBuf, String> {
+    Ok(storage::shared_dir(repo_path)?.join("provision.json"))
+}
+
+/// Kept in the worktree's private git directory so it goes away with the worktree
+fn record_file(worktree_path: &Path) -> Result<PathBuf, String> {
+    let git_dir = with_backend(|git| git.git_dir(worktree_path))?;
+    Ok(git_dir.join("codecraft-provision.json"))
+}
+
+/// Key of a worktree in `RUNS`, the same however its path is spelled
+fn run_key(worktree_path: &str) -> String {
+    Path::new(worktree_path)
+        .canonicalize()
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|_| worktree_path.to_string())
+}
+
+fn emit(app_handle: &AppHandle, worktree_path: &str, update: ProvisionUpdate) {
+    let event = ProvisionEvent {
+        worktree_path: worktree_path.to_string(),
+        update,
+    };
+    let _ = app_handle.emit(PROVISION_EVENT, event);
+}
+
+fn shell(script: &str) -> Command {
+    #[cfg(windows)]
+    {
+        let mut command = Command::new("cmd");
+        command.args(["/C", script]);
+        command
+    }
+    #[cfg(not(windows))]
+    {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        // Own process group, so cancelling reaches whatever the script started
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        command
+    }
+}
+
+/// Stop a step and everything it started
+fn kill_tree(child: &mut Child) {
+    let pid = child.id().to_string();
+
+    #[cfg(windows)]
+    let _ = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid])
+        .status();
+    #[cfg(not(windows))]
+    let _ = Command::new("kill")
+        .args(["-TERM", "--", &format!("-{}", pid)])
+        .status();
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(unix)]
+fn pipe_file(pipe: impl Into<std::os::fd::OwnedFd>) -> File {
+    File::from(pipe.into())
+}
+
+#[cfg(windows)]
+fn pipe_file(pipe: impl Into<std::os::windows::io::OwnedHandle>) -> File {
+    File::from(pipe.into())
+}
+
+/// Wait until `source` can be read without blocking, or until `stop` is set. A read
+/// can't be interrupted once it blocks, so this is what lets a reader give up on a pipe
+/// something left running still holds open.
+#[cfg(unix)]
+fn readable(source: &File, stop: &AtomicBool) -> bool {
+    use std::os::fd::AsRawFd;
+
+    let mut poll = libc::pollfd {
+        fd: source.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    while !stop.load(Ordering::Relaxed) {
+        // SAFETY: `poll` is one valid pollfd and `source` keeps its descriptor open
+        let ready = unsafe { libc::poll(&mut poll, 1, READ_POLL.as_millis() as libc::c_int) };
+        if ready < 0 && std::io::Error::last_os_error().kind() == ErrorKind::Interrupted {
+            continue;
+        }
+        // Data, a hang-up or an error: the read that follows tells which
+        if ready != 0 {
+            return true;
+        }
+    }
+    false
+}
+
+/// Anonymous pipes can't be polled on Windows, so reads there block until the pipe
+/// closes; `taskkill /T` closes it for everything but processes that left the tree.
+#[cfg(windows)]
+fn readable(_source: &File, stop: &AtomicBool) -> bool {
+    !stop.load(Ordering::Relaxed)
+}
+
+/// Emit everything read from `source` as output of `step`, until it closes or `stop`
+/// is set
+fn forward(
+    app_handle: &AppHandle,
+    worktree_path: &str,
+    step: usize,
+    stream: OutputStream,
+    mut source: File,
+    stop: Arc<AtomicBool>,
+) -> thread::JoinHandle<()> {
+    let app_handle = app_handle.clone();
+    let worktree_path = worktree_path.to_string();
+
+    thread::spawn(move || {
+        let mut buffer = [0u8; 4096];
+        while readable(&source, &stop) {
+            match source.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    let data = String::from_utf8_lossy(&buffer[..n]).to_string();
+                    emit(
+                        &app_handle,
+                        &worktree_path,
+                        ProvisionUpdate::Output { step, stream, data },
+                    );
+                }
+            }
+        }
+    })
+}
+
+/// Run one step to completion; `None` means it was cancelled
+fn run_step(
+    app_handle: &AppHandle,
+    worktree_path: &str,
+    index: usize,
+    step: &ProvisionStep,
+    env: &[(&str, String)],
+    cancelled: &AtomicBool,
+) -> Result<Option<ExitStatus>, String> {
+    let mut child = shell(&step.run)
+        .current_dir(worktree_path)
+        .envs(env.iter().map(|(key, value)| (*key, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start `{}`: {}", step.run, e))?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut readers = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        readers.push(forward(
+            app_handle,
+            worktree_path,
+            index,
+            OutputStream::Stdout,
+            pipe_file(stdout),
+            stop.clone(),
+        ));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(forward(
+            app_handle,
+            worktree_path,
+            index,
+            OutputStream::Stderr,
+            pipe_file(stderr),
+            stop.clone(),
+        ));
+    }
+
+    let status = loop {
+        if cancelled.load(Ordering::Relaxed) {
+            kill_tree(&mut child);
+            break None;
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) => thread::sleep(Duration::from_millis(50)),
+            Err(e) => {
+                kill_tree(&mut child);
+                return Err(format!("Failed to wait for `{}`: {}", step.run, e));
+            }
+        }
+    };
+
+    // After a cancel the output no longer matters; otherwise drain what is left for
+    // the grace period, then stop the readers, closing the pipes
+    if status.is_some() {
+        let deadline = Instant::now() + OUTPUT_GRACE;
+        while readers.iter().any(|reader| !reader.is_finished()) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+    stop.store(true, Ordering::Relaxed);
+    for reader in readers {
+        // Readers that can't poll may stay blocked, so only those are left behind
+        if cfg!(unix) || reader.is_finished() {
+            let _ = reader.join();
+        }
+    }
+
+    Ok(status)
+}
+
+fn run_recipe(
+    app_handle: &AppHandle,
+    worktree_path: &str,
+    recipe: &ProvisionRecipe,
+    env: &[(&str, String)],
+    cancelled: &AtomicBool,
+    started_at: u64,
+) -> ProvisionRecord {
+    let finish = |state, step, message| ProvisionRecord {
+        state,
+        started_at,
+        finished_at: Some(now()),
+        step,
+        message,
+    };
+
+    for (index, step) in recipe.steps.iter().enumerate() {
+        let name = step.name.clone().unwrap_or_else(|| step.run.clone());
+        emit(
+            app_handle,
+            worktree_path,
+            ProvisionUpdate::StepStarted { step: index, name },
+        );
+
+        match run_step(app_handle, worktree_path, index, step, env, cancelled) {
+            Ok(Some(status)) if status.success() => {}
+            Ok(Some(status)) => {
+                let message = format!("`{}` failed with {}", step.run, status);
+                return finish(ProvisionState::Failed, Some(index), Some(message));
+            }
+            Ok(None) => return finish(ProvisionState::Cancelled, Some(index), None),
+            Err(e) => return finish(ProvisionState::Failed, Some(index), Some(e)),
+        }
+    }
+
+    finish(ProvisionState::Succeeded, None, None)
+}
+
+/// Start the repository's recipe in `worktree_path` in the background, streaming its
+/// output as `provision-event`s. Returns whether there was anything to run.
+pub fn start(app_handle: AppHandle, worktree_path: &str) -> Result<bool, String> {
+    let worktree = Path::new(worktree_path);
+    let recipe: ProvisionRecipe = storage::read_json(&recipe_file(worktree)?)?;
+    if recipe.steps.is_empty() {
+        return Ok(false);
+    }
+
+    let record_file = record_file(worktree)?;
+    let main_worktree = with_backend(|git| git.list_worktrees(worktree))?
+        .into_iter()
+        .find(|wt| wt.is_main)
+        .map(|wt| wt.path)
+        .unwrap_or_default();
+    let env = vec![
+        ("CODECRAFT_WORKTREE", worktree_path.to_string()),
+        ("CODECRAFT_MAIN_WORKTREE", main_worktree),
+    ];
+
+    let key = run_key(worktree_path);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    {
+        let mut runs = RUNS.lock();
+        if runs.contains_key(&key) {
+            return Err(format!(
+                "Provisioning is already running in {}",
+                worktree_path
+            ));
+        }
+        runs.insert(key.clone(), cancelled.clone());
+    }
+
+    let started_at = now();
+    let running = ProvisionRecord {
+        state: ProvisionState::Running,
+        started_at,
+        finished_at: None,
+        step: None,
+        message: None,
+    };
+    if let Err(e) = storage::write_json(&record_file, &running) {
+        RUNS.lock().remove(&key);
+        return Err(e);
+    }
+
+    let worktree_path = worktree_path.to_string();
+    thread::spawn(move || {
+        let record = run_recipe(
+            &app_handle,
+            &worktree_path,
+            &recipe,
+            &env,
+            &cancelled,
+            started_at,
+        );
+
+        // Recorded and forgotten together, so the outcome is never seen while the run
+        // still counts as in progress, and before the event so a listener can start
+        // another run right away
+        {
+            let mut runs = RUNS.lock();
+            if let Err(e) = storage::write_json(&record_file, &record) {
+                eprintln!("Failed to record provisioning of {}: {}", worktree_path, e);
+            }
+            runs.remove(&key);
+        }
+        emit(
+            &app_handle,
+            &worktree_path,
+            ProvisionUpdate::Finished { record },
+        );
+    });
+
+    Ok(true)
+}
+
+/// Get the provisioning recipe of a repository
+#[tauri::command]
+pub async fn get_provision_recipe(repo_path: String) -> Result<ProvisionRecipe, String> {
+    storage::read_json(&recipe_file(Path::new(&repo_path))?)
+}
+
+/// Replace the provisioning recipe of a repository
+#[tauri::command]
+pub async fn set_provision_recipe(
+    repo_path: String,
+    recipe: ProvisionRecipe,
+) -> Result<(), String> {
+    storage::write_json(&recipe_file(Path::new(&repo_path))?, &recipe)
+}
+
+/// Whether a provisioning run is in progress in `worktree_path`
+pub fn is_running(worktree_path: &str) -> bool {
+    RUNS.lock().contains_key(&run_key(worktree_path))
+}
+
+/// Run the recipe again in an existing worktree. Returns false if the recipe is empty.
+#[tauri::command]
+pub async fn run_provisioning(
+    app_handle: AppHandle,
+    worktree_path: String,
+) -> Result<bool, String> {
+    start(app_handle, &worktree_path)
+}
+
+/// Stop a running recipe; the step in progress is killed
+#[tauri::command]
+pub async fn cancel_provisioning(worktree_path: String) -> Result<(), String> {
+    match RUNS.lock().get(&run_key(&worktree_path)) {
+        Some(cancelled) => {
+            cancelled.store(true, Ordering::Relaxed);
+            Ok(())
+        }
+        None => Err(format!("Provisioning is not running in {}", worktree_path)),
+    }
+}
+
+/// Get the outcome of the last provisioning run of a worktree, if there was one
+#[tauri::command]
+pub async fn get_provision_status(
+    worktree_path: String,
+) -> Result<Option<ProvisionRecord>, String> {
+    let file = record_file(Path::new(&worktree_path))?;
+    // Read under the lock so a run can't finish between reading its record and checking it
+    let runs = RUNS.lock();
+    let Some(mut record) = storage::read_json::<Option<ProvisionRecord>>(&file)? else {
+        return Ok(None);
+    };
+
+    // A run that is recorded as running but isn't was cut short by the app exiting
+    if record.state == ProvisionState::Running && !runs.contains_key(&run_key(&worktree_path)) {
+        record.state = ProvisionState::Failed;
+        record.message = Some("Interrupted before finishing".to_string());
+    }
+
+    Ok(Some(record))
+}
@@ -0,0 +1,45 @@
+use crate::commands::lineage;
+use crate::commands::worktree::MergeResult;
+use crate::git::with_backend;
+use std::path::Path;
+
+/// Rebase a worktree's branch onto `onto`, or onto its parent in the lineage when
+/// omitted. A conflicting rebase is left in progress for `continue_rebase`,
+/// `skip_rebase` or `abort_rebase`.
+#[tauri::command]
+pub async fn rebase_worktree(
+    worktree_path: String,
+    onto: Option<String>,
+) -> Result<MergeResult, String> {
+    let worktree = Path::new(&worktree_path);
+    let onto = match onto {
+        Some(onto) => onto,
+        None => lineage::worktree_parent(worktree)?,
+    };
+
+    Ok(with_backend(|git| git.rebase(worktree, &onto))?)
+}
+
+/// Resume a stopped rebase once its conflicts are resolved and staged
+#[tauri::command]
+pub async fn continue_rebase(worktree_path: String) -> Result<MergeResult, String> {
+    Ok(with_backend(|git| {
+        git.continue_rebase(Path::new(&worktree_path))
+    })?)
+}
+
+/// Drop the commit a rebase stopped on and carry on with the rest
+#[tauri::command]
+pub async fn skip_rebase(worktree_path: String) -> Result<MergeResult, String> {
+    Ok(with_backend(|git| {
+        git.skip_rebase(Path::new(&worktree_path))
+    })?)
+}
+
+/// Abandon a rebase and return the branch to where it was before
+#[tauri::command]
+pub async fn abort_rebase(worktree_path: String) -> Result<(), String> {
+    Ok(with_backend(|git| {
+        git.abort_rebase(Path::new(&worktree_path))
+    })?)
+}
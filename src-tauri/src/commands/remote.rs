@@ -0,0 +1,159 @@
+use crate::commands::askpass::AskpassSession;
+use crate::commands::worktree::MergeResult;
+use crate::git::{with_backend, GitBackend, GitResult, Transfer};
+use serde::Serialize;
+use std::path::Path;
+use tauri::{AppHandle, Emitter};
+
+const REMOTE_PROGRESS_EVENT: &str = "remote-progress";
+
+const DEFAULT_REMOTE: &str = "origin";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteOperation {
+    Fetch,
+    Pull,
+    Push,
+}
+
+/// Payload of the `remote-progress` event
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteProgress {
+    /// Repository or worktree path the operation was started with
+    pub path: String,
+    pub operation: RemoteOperation,
+    /// The line git printed, e.g. `Receiving objects:  45% (9/20)`
+    pub message: String,
+    /// How far through its current phase the operation is, when git says
+    pub percent: Option<u8>,
+}
+
+/// The percentage in a progress line like `Writing objects:  45% (9/20)`
+fn percent(line: &str) -> Option<u8> {
+    let (before, _) = line.split_once('%')?;
+    before.rsplit(' ').next()?.parse().ok()
+}
+
+/// Run a remote operation with its progress emitted as `remote-progress` events and
+/// its credential prompts sent to the UI
+fn run_transfer<T>(
+    app_handle: &AppHandle,
+    path: &str,
+    operation: RemoteOperation,
+    op: impl Fn(&dyn GitBackend, &Transfer) -> GitResult<T>,
+) -> Result<T, String> {
+    let askpass = AskpassSession::start(app_handle, path)?;
+    let on_progress = |line: &str| {
+        let event = RemoteProgress {
+            path: path.to_string(),
+            operation,
+            message: line.to_string(),
+            percent: percent(line),
+        };
+        let _ = app_handle.emit(REMOTE_PROGRESS_EVENT, event);
+    };
+    let transfer = Transfer {
+        on_progress: &on_progress,
+        env: askpass.env(),
+    };
+
+    Ok(with_backend(|git| op(git, &transfer))?)
+}
+
+/// Fetch `remote`, or every remote when omitted, so remote-tracking branches (and the
+/// ahead/behind counts against them) are current
+#[tauri::command]
+pub async fn fetch_remote(
+    app_handle: AppHandle,
+    repo_path: String,
+    remote: Option<String>,
+    prune: Option<bool>,
+) -> Result<(), String> {
+    run_transfer(
+        &app_handle,
+        &repo_path,
+        RemoteOperation::Fetch,
+        |git, transfer| {
+            git.fetch(
+                Path::new(&repo_path),
+                remote.as_deref(),
+                prune.unwrap_or(false),
+                transfer,
+            )
+        },
+    )
+}
+
+/// Pull the upstream of a worktree's branch, merging unless `rebase` is set. Conflicts
+/// are left in the worktree to resolve like those of `merge_branch`.
+#[tauri::command]
+pub async fn pull_branch(
+    app_handle: AppHandle,
+    worktree_path: String,
+    rebase: Option<bool>,
+) -> Result<MergeResult, String> {
+    run_transfer(
+        &app_handle,
+        &worktree_path,
+        RemoteOperation::Pull,
+        |git, transfer| git.pull(Path::new(&worktree_path), rebase.unwrap_or(false), transfer),
+    )
+}
+
+/// Push a worktree's branch to the branch of the same name on `remote` (`origin` by
+/// default), making that its upstream if `set_upstream` is set
+#[tauri::command]
+pub async fn push_branch(
+    app_handle: AppHandle,
+    worktree_path: String,
+    remote: Option<String>,
+    set_upstream: Option<bool>,
+    force_with_lease: Option<bool>,
+) -> Result<(), String> {
+    let remote = remote.unwrap_or_else(|| DEFAULT_REMOTE.to_string());
+    run_transfer(
+        &app_handle,
+        &worktree_path,
+        RemoteOperation::Push,
+        |git, transfer| {
+            git.push(
+                Path::new(&worktree_path),
+                &remote,
+                set_upstream.unwrap_or(false),
+                force_with_lease.unwrap_or(false),
+                transfer,
+            )
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::percent;
+
+    #[test]
+    fn percent_reads_progress_lines() {
+        assert_eq!(percent("Receiving objects:  45% (9/20)"), Some(45));
+        assert_eq!(
+            percent("Writing objects: 100% (20/20), 1.95 KiB | 1.95 MiB/s, done."),
+            Some(100)
+        );
+        assert_eq!(
+            percent("remote: Compressing objects:   0% (1/150)"),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn percent_ignores_other_lines() {
+        assert_eq!(percent("From github.com:example/repo"), None);
+        assert_eq!(
+            percent(" * [new branch]      main       -> origin/main"),
+            None
+        );
+        assert_eq!(percent("100%"), Some(100));
+        assert_eq!(percent("done with %"), None);
+        assert_eq!(percent("Resolving deltas: 999% (1/1)"), None);
+    }
+}
@@ -0,0 +1,311 @@
+use crate::commands::lineage;
+use crate::commands::storage;
+use crate::commands::worktree::{MergeResult, Worktree};
+use crate::git::with_backend;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+/// What happened to one branch of a restack
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestackStatus {
+    Restacked,
+    /// Already based on the tip of its parent
+    UpToDate,
+    /// Not checked out in any worktree, so there was nowhere to rebase it
+    Skipped,
+    /// Stopped with conflicts left in the branch's worktree
+    Conflicted,
+    /// The rebase didn't start, e.g. because the worktree has uncommitted changes, was
+    /// aborted outside the restack, or stopped without conflicts
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BranchRestack {
+    pub branch: String,
+    pub parent: String,
+    pub status: RestackStatus,
+    pub worktree_path: Option<String>,
+    pub message: String,
+    pub conflicts: Vec<String>,
+    /// Tip of the branch after it was restacked
+    pub commit: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestackResult {
+    /// Branch whose descendants are being restacked
+    pub root: String,
+    /// Whether every descendant was handled; `false` means the restack stopped at the
+    /// last entry of `branches` and waits for `continue_restack` or `abort_restack`
+    pub success: bool,
+    pub branches: Vec<BranchRestack>,
+    /// Branches still to restack after the one that stopped
+    pub pending: Vec<String>,
+}
+
+/// A descendant waiting to be rebased from `base` onto `parent`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingBranch {
+    branch: String,
+    parent: String,
+    /// Old tip of the parent the branch's own commits start after
+    base: String,
+}
+
+/// A stopped restack, stored in `<git common dir>/codecraft/restack.json`. The first
+/// queued branch is the one that stopped.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RestackState {
+    root: String,
+    queue: Vec<PendingBranch>,
+    /// Worktree the first branch stopped in with a rebase in progress. It is kept here
+    /// because the worktree shows a detached HEAD until the rebase finishes.
+    conflicted_in: Option<String>,
+}
+
+fn state_file(repo_path: &Path) -> Result<PathBuf, String> {
+    Ok(storage::shared_dir(repo_path)?.join("restack.json"))
+}
+
+/// Descendants of `root` in the order they must be rebased, parents before children.
+/// Branches without a recorded parent hang off the main worktree's branch, as in the graph.
+fn descendants(repo: &Path, root: &str) -> Result<Vec<(String, String)>, String> {
+    let worktrees = with_backend(|git| git.list_worktrees(repo))?;
+    let mut parents = lineage::load(repo)?.parents;
+
+    let main_branch = worktrees
+        .iter()
+        .find(|wt| wt.is_main)
+        .and_then(|wt| wt.branch.clone());
+    if let Some(main_branch) = main_branch {
+        for branch in worktrees.iter().filter_map(|wt| wt.branch.as_ref()) {
+            if *branch != main_branch && !parents.contains_key(branch) {
+                parents.insert(branch.clone(), main_branch.clone());
+            }
+        }
+    }
+
+    let mut children: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (branch, parent) in &parents {
+        children.entry(parent).or_default().push(branch);
+    }
+
+    let mut order = Vec::new();
+    let mut seen = BTreeSet::from([root]);
+    let mut queue = VecDeque::from([root]);
+    while let Some(parent) = queue.pop_front() {
+        for child in children.get(parent).into_iter().flatten() {
+            if seen.insert(child) {
+                order.push((child.to_string(), parent.to_string()));
+                queue.push_back(child);
+            }
+        }
+    }
+
+    Ok(order)
+}
+
+fn is_rebasing(worktree: &Path) -> Result<bool, String> {
+    let git_dir = with_backend(|git| git.git_dir(worktree))?;
+    Ok(git_dir.join("rebase-merge").exists() || git_dir.join("rebase-apply").exists())
+}
+
+fn outcome(pending: &PendingBranch, worktree: &Worktree, result: MergeResult) -> BranchRestack {
+    let status = match (result.success, result.conflicts.is_empty()) {
+        (true, _) => RestackStatus::Restacked,
+        (false, false) => RestackStatus::Conflicted,
+        (false, true) => RestackStatus::Failed,
+    };
+
+    BranchRestack {
+        branch: pending.branch.clone(),
+        parent: pending.parent.clone(),
+        status,
+        worktree_path: Some(worktree.path.clone()),
+        message: result.message,
+        conflicts: result.conflicts,
+        commit: result.commit,
+    }
+}
+
+/// Rebase one queued branch, or finish the rebase it stopped in at `resume_in`
+fn restack_branch(
+    repo: &Path,
+    pending: &PendingBranch,
+    worktrees: &[Worktree],
+    resume_in: Option<&str>,
+) -> Result<BranchRestack, String> {
+    let worktree = match resume_in {
+        Some(path) => worktrees.iter().find(|wt| wt.path == path),
+        None => worktrees
+            .iter()
+            .find(|wt| wt.branch.as_ref() == Some(&pending.branch)),
+    };
+    let Some(worktree) = worktree else {
+        return Ok(BranchRestack {
+            branch: pending.branch.clone(),
+            parent: pending.parent.clone(),
+            status: RestackStatus::Skipped,
+            worktree_path: None,
+            message: format!("{} is not checked out in any worktree", pending.branch),
+            conflicts: vec![],
+            commit: None,
+        });
+    };
+    let path = Path::new(&worktree.path);
+
+    if resume_in.is_some() {
+        // The rebase may already have been finished from the conflict view, or aborted
+        // there, which leaves the branch where it was before the restack
+        let result = if is_rebasing(path)? {
+            with_backend(|git| git.continue_rebase(path))?
+        } else if with_backend(|git| git.ahead_behind(path, "HEAD", &pending.parent))?.1 == 0 {
+            MergeResult {
+                success: true,
+                message: "Rebase successful".to_string(),
+                conflicts: vec![],
+                worktree_path: Some(worktree.path.clone()),
+                commit: Some(with_backend(|git| git.resolve_commit(path, "HEAD"))?),
+            }
+        } else {
+            MergeResult {
+                success: false,
+                message: format!("The rebase of {} was aborted", pending.branch),
+                conflicts: vec![],
+                worktree_path: Some(worktree.path.clone()),
+                commit: None,
+            }
+        };
+        return Ok(outcome(pending, worktree, result));
+    }
+
+    let parent_tip = with_backend(|git| git.resolve_commit(repo, &pending.parent))?;
+    if parent_tip == pending.base {
+        return Ok(BranchRestack {
+            branch: pending.branch.clone(),
+            parent: pending.parent.clone(),
+            status: RestackStatus::UpToDate,
+            worktree_path: Some(worktree.path.clone()),
+            message: format!("{} is up to date with {}", pending.branch, pending.parent),
+            conflicts: vec![],
+            commit: Some(worktree.head.clone()),
+        });
+    }
+
+    let result = with_backend(|git| git.rebase_onto(path, &pending.parent, &pending.base))?;
+    Ok(outcome(pending, worktree, result))
+}
+
+/// Work through `queue`, saving what is left if a branch stops
+fn run(
+    repo: &Path,
+    root: String,
+    queue: Vec<PendingBranch>,
+    resume_in: Option<String>,
+) -> Result<RestackResult, String> {
+    let worktrees = with_backend(|git| git.list_worktrees(repo))?;
+    let mut branches = Vec::new();
+
+    for (index, pending) in queue.iter().enumerate() {
+        let resume_in = resume_in.as_deref().filter(|_| index == 0);
+        let restacked = restack_branch(repo, pending, &worktrees, resume_in)?;
+        let status = restacked.status;
+        let worktree_path = restacked.worktree_path.clone();
+        branches.push(restacked);
+
+        if matches!(status, RestackStatus::Conflicted | RestackStatus::Failed) {
+            // A rebase can also stop without conflicts, e.g. on untracked files a pick
+            // would overwrite, and then has to be resumed rather than started again
+            let conflicted_in = match worktree_path {
+                Some(path) if is_rebasing(Path::new(&path))? => Some(path),
+                _ => None,
+            };
+            let state = RestackState {
+                root: root.clone(),
+                queue: queue[index..].to_vec(),
+                conflicted_in,
+            };
+            storage::write_json(&state_file(repo)?, &state)?;
+
+            return Ok(RestackResult {
+                root,
+                success: false,
+                branches,
+                pending: queue[index + 1..]
+                    .iter()
+                    .map(|pending| pending.branch.clone())
+                    .collect(),
+            });
+        }
+    }
+
+    storage::write_json(&state_file(repo)?, &RestackState::default())?;
+    Ok(RestackResult {
+        root,
+        success: true,
+        branches,
+        pending: vec![],
+    })
+}
+
+/// Rebase every branch below `branch_name` in the lineage onto its parent, parents
+/// before children. Stops at the first branch that conflicts or fails to rebase,
+/// leaving the rest for `continue_restack`.
+#[tauri::command]
+pub async fn restack(repo_path: String, branch_name: String) -> Result<RestackResult, String> {
+    let repo = Path::new(&repo_path);
+    let state: RestackState = storage::read_json(&state_file(repo)?)?;
+    if !state.queue.is_empty() {
+        return Err(format!(
+            "A restack of {} is already in progress; continue or abort it first",
+            state.root
+        ));
+    }
+
+    // Find every old base before anything moves: later branches start after the old
+    // tips of parents that are about to be rebased
+    let mut queue = Vec::new();
+    for (branch, parent) in descendants(repo, &branch_name)? {
+        let base = with_backend(|git| git.fork_point(repo, &parent, &branch))?;
+        queue.push(PendingBranch {
+            branch,
+            parent,
+            base,
+        });
+    }
+
+    run(repo, branch_name, queue, None)
+}
+
+/// Carry on with a stopped restack once the conflicts in the branch it stopped at are
+/// resolved and staged, or whatever made it fail is fixed
+#[tauri::command]
+pub async fn continue_restack(repo_path: String) -> Result<RestackResult, String> {
+    let repo = Path::new(&repo_path);
+    let state: RestackState = storage::read_json(&state_file(repo)?)?;
+    if state.queue.is_empty() {
+        return Err("No restack is in progress".to_string());
+    }
+
+    run(repo, state.root, state.queue, state.conflicted_in)
+}
+
+/// Abandon a stopped restack. The rebase it stopped in is aborted; branches already
+/// restacked stay where they are.
+#[tauri::command]
+pub async fn abort_restack(repo_path: String) -> Result<(), String> {
+    let repo = Path::new(&repo_path);
+    let file = state_file(repo)?;
+    let state: RestackState = storage::read_json(&file)?;
+
+    if let Some(path) = state.conflicted_in.as_deref().map(Path::new) {
+        if path.exists() && is_rebasing(path)? {
+            with_backend(|git| git.abort_rebase(path))?;
+        }
+    }
+
+    storage::write_json(&file, &RestackState::default())
+}
@@ -0,0 +1,158 @@
+use crate::commands::commit::CommitInfo;
+use crate::commands::worktree::MergeResult;
+use crate::git::with_backend;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A stash. Stashes live in the shared `refs/stash`, so every worktree sees the same
+/// list; `branch` and `worktree_path` tell where one came from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashEntry {
+    /// Position in the list (`stash@{index}`), which shifts as stashes come and go.
+    /// Commands take the stash commit's SHA instead.
+    pub index: usize,
+    /// Branch the stash was made on, `None` if HEAD was detached
+    pub branch: Option<String>,
+    /// The message given to `push_stash`, or git's `WIP on ...` description
+    pub message: String,
+    /// Worktree that has `branch` checked out now, if any
+    pub worktree_path: Option<String>,
+    pub commit: CommitInfo,
+}
+
+/// Split a stash subject, `On <branch>: <message>` or `WIP on <branch>: <sha> <subject>`
+fn parse_subject(subject: &str) -> (Option<String>, String) {
+    let described = subject
+        .strip_prefix("On ")
+        .map(|rest| (rest, false))
+        .or_else(|| subject.strip_prefix("WIP on ").map(|rest| (rest, true)));
+
+    match described.and_then(|(rest, wip)| Some((rest.split_once(": ")?, wip))) {
+        Some(((branch, message), wip)) => {
+            let branch = (branch != "(no branch)").then(|| branch.to_string());
+            let message = if wip { subject } else { message };
+            (branch, message.to_string())
+        }
+        None => (None, subject.to_string()),
+    }
+}
+
+fn stashes(repo: &Path) -> Result<Vec<StashEntry>, String> {
+    let commits = with_backend(|git| git.list_stashes(repo))?;
+    let worktrees = with_backend(|git| git.list_worktrees(repo))?;
+
+    Ok(commits
+        .into_iter()
+        .enumerate()
+        .map(|(index, commit)| {
+            let (branch, message) = parse_subject(&commit.summary);
+            let worktree_path = branch.as_ref().and_then(|branch| {
+                worktrees
+                    .iter()
+                    .find(|wt| wt.branch.as_ref() == Some(branch))
+                    .map(|wt| wt.path.clone())
+            });
+            StashEntry {
+                index,
+                branch,
+                message,
+                worktree_path,
+                commit,
+            }
+        })
+        .collect())
+}
+
+/// Drop the stash whose commit is `stash`, wherever it sits in the list now
+fn drop_by_sha(repo: &Path, stash: &str) -> Result<(), String> {
+    let index = with_backend(|git| git.list_stashes(repo))?
+        .iter()
+        .position(|commit| commit.sha == stash)
+        .ok_or_else(|| format!("No such stash: {}", stash))?;
+    let reflog_name = format!("stash@{{{}}}", index);
+    with_backend(|git| git.stash_drop(repo, &reflog_name))?;
+    Ok(())
+}
+
+/// Apply a stash to a worktree and drop it unless it conflicted or `keep` is set
+fn apply_and_drop(worktree: &Path, stash: &str, keep: bool) -> Result<MergeResult, String> {
+    let result = with_backend(|git| git.stash_apply(worktree, stash))?;
+    if result.success && !keep {
+        drop_by_sha(worktree, stash)?;
+    }
+    Ok(result)
+}
+
+/// List the repository's stashes, newest first
+#[tauri::command]
+pub async fn list_stashes(repo_path: String) -> Result<Vec<StashEntry>, String> {
+    stashes(Path::new(&repo_path))
+}
+
+/// Stash a worktree's changes, only those to `paths` if any are given. Returns the new
+/// stash, or `None` if there was nothing to stash.
+#[tauri::command]
+pub async fn push_stash(
+    worktree_path: String,
+    message: Option<String>,
+    include_untracked: Option<bool>,
+    paths: Option<Vec<String>>,
+) -> Result<Option<StashEntry>, String> {
+    let worktree = Path::new(&worktree_path);
+    let created = with_backend(|git| {
+        git.stash_push(
+            worktree,
+            message.as_deref(),
+            include_untracked.unwrap_or(false),
+            paths.as_deref().unwrap_or_default(),
+        )
+    })?;
+
+    if !created {
+        return Ok(None);
+    }
+    Ok(stashes(worktree)?.into_iter().next())
+}
+
+/// Apply a stash to a worktree and keep it
+#[tauri::command]
+pub async fn apply_stash(worktree_path: String, stash: String) -> Result<MergeResult, String> {
+    apply_and_drop(Path::new(&worktree_path), &stash, true)
+}
+
+/// Apply a stash to a worktree and drop it. A stash that conflicts is kept.
+#[tauri::command]
+pub async fn pop_stash(worktree_path: String, stash: String) -> Result<MergeResult, String> {
+    apply_and_drop(Path::new(&worktree_path), &stash, false)
+}
+
+/// Drop a stash without applying it
+#[tauri::command]
+pub async fn drop_stash(repo_path: String, stash: String) -> Result<(), String> {
+    drop_by_sha(Path::new(&repo_path), &stash)
+}
+
+/// Move a stash, usually made in another worktree, onto `target_worktree_path`. The
+/// stash is dropped once it applies cleanly unless `keep` is set; on conflicts it is
+/// kept and the conflicted files are left in the target to resolve.
+#[tauri::command]
+pub async fn transfer_stash(
+    stash: String,
+    target_worktree_path: String,
+    keep: Option<bool>,
+) -> Result<MergeResult, String> {
+    let target = Path::new(&target_worktree_path);
+    let entry = stashes(target)?
+        .into_iter()
+        .find(|entry| entry.commit.sha == stash)
+        .ok_or_else(|| format!("No such stash: {}", stash))?;
+
+    let mut result = apply_and_drop(target, &stash, keep.unwrap_or(false))?;
+    if result.success {
+        result.message = match entry.branch {
+            Some(branch) => format!("Stash from {} applied", branch),
+            None => "Stash applied".to_string(),
+        };
+    }
+    Ok(result)
+}
@@ -0,0 +1,63 @@
+use crate::git::with_backend;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// How a file differs on one side of the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChange {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Copied,
+    TypeChanged,
+}
+
+/// Which sides of a merge touched an unmerged file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    BothModified,
+    BothAdded,
+    BothDeleted,
+    AddedByUs,
+    AddedByThem,
+    DeletedByUs,
+    DeletedByThem,
+}
+
+/// State of a submodule's checkout relative to the superproject
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmoduleState {
+    pub commit_changed: bool,
+    pub has_tracked_changes: bool,
+    pub has_untracked_changes: bool,
+}
+
+/// One entry of `git status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileStatus {
+    pub path: String,
+    /// Path the file was renamed or copied from
+    pub original_path: Option<String>,
+    /// Change between HEAD and the index
+    pub staged: Option<FileChange>,
+    /// Change between the index and the working tree
+    pub unstaged: Option<FileChange>,
+    pub untracked: bool,
+    pub ignored: bool,
+    pub conflict: Option<ConflictKind>,
+    pub submodule: Option<SubmoduleState>,
+}
+
+/// Get the per-file status of a worktree
+#[tauri::command]
+pub async fn get_worktree_status(
+    worktree_path: String,
+    include_ignored: Option<bool>,
+) -> Result<Vec<FileStatus>, String> {
+    Ok(with_backend(|git| {
+        git.status(Path::new(&worktree_path), include_ignored.unwrap_or(false))
+    })?)
+}
@@ -0,0 +1,37 @@
+use crate::git::with_backend;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory for state shared by every worktree of a repository:
+/// `<git common dir>/codecraft`
+pub fn shared_dir(repo_path: &Path) -> Result<PathBuf, String> {
+    let common_dir = with_backend(|git| git.common_dir(repo_path))?;
+    Ok(common_dir.join("codecraft"))
+}
+
+/// Read a JSON file, or the default value if it doesn't exist yet
+pub fn read_json<T: DeserializeOwned + Default>(file: &Path) -> Result<T, String> {
+    match fs::read_to_string(file) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", file.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("Failed to read {}: {}", file.display(), e)),
+    }
+}
+
+pub fn write_json<T: Serialize>(file: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
+    }
+
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", file.display(), e))?;
+
+    // Write to a sibling file and rename so a crash never leaves a truncated file
+    let tmp = file.with_extension("json.tmp");
+    fs::write(&tmp, content).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, file).map_err(|e| format!("Failed to write {}: {}", file.display(), e))
+}
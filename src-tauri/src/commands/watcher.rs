@@ -0,0 +1,276 @@
+use crate::commands::worktree::Worktree;
+use crate::git::with_backend;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+const REPOSITORY_EVENT: &str = "repository-event";
+
+/// Quiet period after the last change before the repository is looked at again
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Longest a steady stream of changes (a rebase, a big fetch) can hold events back
+const MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// Parts of the git directory that change without anything visible changing
+const IGNORED_DIRS: [&str; 3] = ["objects", "logs", "codecraft"];
+
+// Active watchers by git common dir; dropping one stops its thread
+lazy_static::lazy_static! {
+    static ref WATCHERS: Mutex<HashMap<PathBuf, RecommendedWatcher>> = Mutex::new(HashMap::new());
+}
+
+/// A ref that was created (`old` is `None`), moved, or deleted (`new` is `None`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefUpdate {
+    pub ref_name: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum RepositoryChange {
+    WorktreeAdded {
+        worktree: Worktree,
+    },
+    WorktreeRemoved {
+        path: String,
+    },
+    /// A worktree's HEAD points at another commit or branch
+    HeadMoved {
+        worktree_path: String,
+        branch: Option<String>,
+        old_head: String,
+        new_head: String,
+    },
+    RefsChanged {
+        refs: Vec<RefUpdate>,
+    },
+    /// A worktree's index was written, e.g. by staging or committing
+    StatusChanged {
+        worktree_path: String,
+    },
+}
+
+/// Payload of the `repository-event` event
+#[derive(Debug, Clone, Serialize)]
+pub struct RepositoryEvent {
+    /// Path the repository is watched through
+    pub repo_path: String,
+    #[serde(flatten)]
+    pub change: RepositoryChange,
+}
+
+/// Modification time and size of an index file
+type IndexStamp = (SystemTime, u64);
+
+/// What the watcher compares between changes
+#[derive(Debug)]
+struct Snapshot {
+    worktrees: BTreeMap<String, (Worktree, Option<IndexStamp>)>,
+    refs: BTreeMap<String, String>,
+}
+
+impl Snapshot {
+    fn take(repo_path: &Path, common_dir: &Path) -> Result<Self, String> {
+        let worktrees = with_backend(|git| git.list_worktrees(repo_path))?
+            .into_iter()
+            .map(|worktree| {
+                let index = if worktree.is_main {
+                    Some(common_dir.join("index"))
+                } else {
+                    worktree
+                        .name
+                        .as_ref()
+                        .map(|name| common_dir.join("worktrees").join(name).join("index"))
+                };
+                let stamp = index
+                    .and_then(|index| fs::metadata(index).ok())
+                    .and_then(|metadata| Some((metadata.modified().ok()?, metadata.len())));
+                (worktree.path.clone(), (worktree, stamp))
+            })
+            .collect();
+
+        let refs = with_backend(|git| git.ref_targets(repo_path, "refs/"))?
+            .into_iter()
+            .collect();
+
+        Ok(Snapshot { worktrees, refs })
+    }
+
+    /// What happened between `self` and `next`
+    fn changes(&self, next: &Snapshot) -> Vec<RepositoryChange> {
+        let mut changes = Vec::new();
+
+        for (path, (worktree, index)) in &next.worktrees {
+            let Some((old, old_index)) = self.worktrees.get(path) else {
+                changes.push(RepositoryChange::WorktreeAdded {
+                    worktree: worktree.clone(),
+                });
+                continue;
+            };
+
+            if old.head != worktree.head || old.branch != worktree.branch {
+                changes.push(RepositoryChange::HeadMoved {
+                    worktree_path: path.clone(),
+                    branch: worktree.branch.clone(),
+                    old_head: old.head.clone(),
+                    new_head: worktree.head.clone(),
+                });
+            }
+            if old_index != index {
+                changes.push(RepositoryChange::StatusChanged {
+                    worktree_path: path.clone(),
+                });
+            }
+        }
+
+        for path in self.worktrees.keys() {
+            if !next.worktrees.contains_key(path) {
+                changes.push(RepositoryChange::WorktreeRemoved { path: path.clone() });
+            }
+        }
+
+        let names: BTreeSet<&String> = self.refs.keys().chain(next.refs.keys()).collect();
+        let refs: Vec<RefUpdate> = names
+            .into_iter()
+            .filter_map(|name| {
+                let (old, new) = (self.refs.get(name), next.refs.get(name));
+                (old != new).then(|| RefUpdate {
+                    ref_name: name.clone(),
+                    old: old.cloned(),
+                    new: new.cloned(),
+                })
+            })
+            .collect();
+        if !refs.is_empty() {
+            changes.push(RepositoryChange::RefsChanged { refs });
+        }
+
+        changes
+    }
+}
+
+/// Whether a change to `path` inside the git directory can affect a snapshot
+fn is_relevant(common_dir: &Path, path: &Path) -> bool {
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "lock")
+    {
+        return false;
+    }
+    let Ok(relative) = path.strip_prefix(common_dir) else {
+        return true;
+    };
+
+    let components: Vec<_> = relative.components().map(|c| c.as_os_str()).collect();
+    match components.as_slice() {
+        [first, ..] if IGNORED_DIRS.iter().any(|dir| first == dir) => false,
+        [worktrees, _, logs, ..] if *worktrees == "worktrees" && *logs == "logs" => false,
+        _ => true,
+    }
+}
+
+fn run(
+    app_handle: AppHandle,
+    repo_path: String,
+    common_dir: PathBuf,
+    events: Receiver<Event>,
+    mut snapshot: Snapshot,
+) {
+    let relevant = |event: &Event| {
+        !event.kind.is_access()
+            && event
+                .paths
+                .iter()
+                .any(|path| is_relevant(&common_dir, path))
+    };
+
+    // Ends when the watcher is dropped and the channel disconnects
+    while let Ok(event) = events.recv() {
+        if !relevant(&event) {
+            continue;
+        }
+
+        let first_change = Instant::now();
+        loop {
+            match events.recv_timeout(DEBOUNCE) {
+                Ok(_) if first_change.elapsed() < MAX_DELAY => {}
+                Ok(_) | Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        let next = match Snapshot::take(Path::new(&repo_path), &common_dir) {
+            Ok(next) => next,
+            Err(e) => {
+                eprintln!("Failed to read repository {}: {}", repo_path, e);
+                continue;
+            }
+        };
+
+        for change in snapshot.changes(&next) {
+            let event = RepositoryEvent {
+                repo_path: repo_path.clone(),
+                change,
+            };
+            let _ = app_handle.emit(REPOSITORY_EVENT, event);
+        }
+        snapshot = next;
+    }
+}
+
+/// Watch the git directory of a repository and emit `repository-event`s as its
+/// worktrees, HEADs, refs and indexes change. Watching a repository twice, even
+/// through different worktrees, is a no-op.
+#[tauri::command]
+pub async fn watch_repository(app_handle: AppHandle, repo_path: String) -> Result<(), String> {
+    let repo = Path::new(&repo_path);
+    let common_dir = with_backend(|git| git.common_dir(repo))?;
+
+    if WATCHERS.lock().contains_key(&common_dir) {
+        return Ok(());
+    }
+
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        if let Ok(event) = event {
+            let _ = sender.send(event);
+        }
+    })
+    .map_err(|e| format!("Failed to watch {}: {}", common_dir.display(), e))?;
+    watcher
+        .watch(&common_dir, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {}", common_dir.display(), e))?;
+
+    // Taken without holding the lock: on the CLI backend it runs git, and other
+    // repositories shouldn't wait on that
+    let snapshot = Snapshot::take(repo, &common_dir)?;
+
+    // Another call may have started watching in the meantime; this watcher then goes
+    let mut watchers = WATCHERS.lock();
+    if watchers.contains_key(&common_dir) {
+        return Ok(());
+    }
+    let thread_common_dir = common_dir.clone();
+    thread::spawn(move || run(app_handle, repo_path, thread_common_dir, events, snapshot));
+
+    watchers.insert(common_dir, watcher);
+    Ok(())
+}
+
+/// Stop watching a repository
+#[tauri::command]
+pub async fn unwatch_repository(repo_path: String) -> Result<(), String> {
+    let common_dir = with_backend(|git| git.common_dir(Path::new(&repo_path)))?;
+    WATCHERS.lock().remove(&common_dir);
+    Ok(())
+}
@@ -1,8 +1,18 @@
+use crate::commands::branch;
+use crate::commands::carry;
+use crate::commands::commit::CommitInfo;
+use crate::commands::lineage;
+use crate::commands::provision;
+use crate::git::with_backend;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Unmerged commits listed when `remove_worktree` refuses to delete a branch
+const MAX_UNMERGED_LISTED: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Worktree {
     pub path: String,
     pub head: String,
@@ -10,6 +20,15 @@ pub struct Worktree {
     pub is_bare: bool,
     pub is_detached: bool,
     pub is_main: bool,
+    /// Name of a linked worktree's administrative directory (`.git/worktrees/<name>`),
+    /// which identifies it even after it is moved. `None` for the main worktree.
+    pub name: Option<String>,
+    /// Locked worktrees are left alone by `prune` and can't be removed without force
+    pub is_locked: bool,
+    pub lock_reason: Option<String>,
+    /// The worktree's directory is gone, so `prune_worktrees` would drop it
+    pub is_prunable: bool,
+    pub prunable_reason: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,118 +36,101 @@ pub struct MergeResult {
     pub success: bool,
     pub message: String,
     pub conflicts: Vec<String>,
+    /// Worktree the merge ran in, where any conflicts are left to resolve
+    pub worktree_path: Option<String>,
+    /// Commit the target branch points at after a successful merge
+    pub commit: Option<String>,
+}
+
+/// How `create_worktree` sets up the new worktree's checkout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CreateMode {
+    /// Create `branch_name` at `base_branch`
+    NewBranch,
+    /// Check out the existing local branch `branch_name`
+    Existing,
+    /// Create a branch tracking the remote branch `base_branch`, e.g. `origin/feature`
+    Track,
+    /// Create `branch_name` with no history and an empty working tree
+    Orphan,
+    /// Check out `base_branch` (a tag, SHA or any other revision) with a detached HEAD
+    Detached,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreatedWorktree {
+    #[serde(flatten)]
+    pub worktree: Worktree,
+    /// How the worktree was created, which is picked automatically when not given
+    pub mode: CreateMode,
+    /// Untracked files carried over from the source worktree
+    pub carried: Vec<String>,
+    /// Whether the repository's provisioning recipe was started in the worktree
+    pub provisioning: bool,
+}
+
+/// Outcome of `remove_worktree`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemovedWorktree {
+    /// False when removal was refused because of `unmerged`
+    pub removed: bool,
+    pub branch: Option<String>,
+    /// Commits of the branch its parent doesn't have, newest first and at most
+    /// `MAX_UNMERGED_LISTED` of them
+    pub unmerged: Vec<CommitInfo>,
+    /// Ref the branch tip was archived under
+    pub archive_ref: Option<String>,
+    pub branch_deleted: bool,
+}
+
+/// How `merge_branch` brings the source branch into the target
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeStrategy {
+    /// Fast-forward when possible, otherwise create a merge commit
+    #[default]
+    Merge,
+    /// Stage the combined changes and commit them as one commit with a generated message
+    Squash,
+    /// Rebase the source onto the target, then fast-forward the target
+    Rebase,
+    /// Fast-forward only; fail if the branches have diverged
+    FfOnly,
+    /// Always create a merge commit
+    NoFf,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BranchInfo {
     pub name: String,
+    /// Upstream tracking branch, e.g. `origin/main`
     pub upstream: Option<String>,
+    /// Commits ahead of / behind the upstream; zero without one
     pub ahead: i32,
     pub behind: i32,
-}
-
-/// Parse `git worktree list --porcelain` output
-fn parse_worktree_list(output: &str) -> Vec<Worktree> {
-    let mut worktrees = Vec::new();
-    let mut current_path = String::new();
-    let mut current_head = String::new();
-    let mut current_branch: Option<String> = None;
-    let mut is_bare = false;
-    let mut is_detached = false;
-    let mut is_first = true;
-
-    for line in output.lines() {
-        if line.is_empty() {
-            if !current_path.is_empty() {
-                worktrees.push(Worktree {
-                    path: current_path.clone(),
-                    head: current_head.clone(),
-                    branch: current_branch.clone(),
-                    is_bare,
-                    is_detached,
-                    is_main: is_first,
-                });
-                is_first = false;
-            }
-            current_path.clear();
-            current_head.clear();
-            current_branch = None;
-            is_bare = false;
-            is_detached = false;
-            continue;
-        }
-
-        if let Some(path) = line.strip_prefix("worktree ") {
-            current_path = path.to_string();
-        } else if let Some(head) = line.strip_prefix("HEAD ") {
-            current_head = head.to_string();
-        } else if let Some(branch) = line.strip_prefix("branch ") {
-            current_branch = Some(branch.replace("refs/heads/", ""));
-        } else if line == "bare" {
-            is_bare = true;
-        } else if line == "detached" {
-            is_detached = true;
-        }
-    }
-
-    // Handle last worktree if output doesn't end with empty line
-    if !current_path.is_empty() {
-        worktrees.push(Worktree {
-            path: current_path,
-            head: current_head,
-            branch: current_branch,
-            is_bare,
-            is_detached,
-            is_main: is_first,
-        });
-    }
-
-    worktrees
+    /// Parent branch from the lineage, or the main worktree's branch
+    pub parent_branch: Option<String>,
+    /// Commits ahead of / behind the parent branch; zero without one
+    pub parent_ahead: i32,
+    pub parent_behind: i32,
+    pub last_commit: Option<CommitInfo>,
 }
 
 /// List all worktrees with parsed metadata
 #[tauri::command]
 pub async fn list_worktrees(repo_path: String) -> Result<Vec<Worktree>, String> {
-    let output = Command::new("git")
-        .args(["worktree", "list", "--porcelain"])
-        .current_dir(&repo_path)
-        .output()
-        .map_err(|e| format!("Failed to execute git command: {}", e))?;
-
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    Ok(parse_worktree_list(&stdout))
+    Ok(with_backend(|git| {
+        git.list_worktrees(Path::new(&repo_path))
+    })?)
 }
 
 /// Get the main repository path (handles both main repo and worktree)
 #[tauri::command]
 pub async fn get_main_repo_path(current_path: String) -> Result<String, String> {
-    let output = Command::new("git")
-        .args(["rev-parse", "--git-common-dir"])
-        .current_dir(&current_path)
-        .output()
-        .map_err(|e| format!("Failed to execute git command: {}", e))?;
-
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
-    }
-
-    let git_common = String::from_utf8_lossy(&output.stdout).trim().to_string();
-
-    // Get the absolute path
-    let git_path = if Path::new(&git_common).is_absolute() {
-        git_common
-    } else {
-        Path::new(&current_path)
-            .join(&git_common)
-            .canonicalize()
-            .map_err(|e| format!("Failed to resolve path: {}", e))?
-            .to_string_lossy()
-            .to_string()
-    };
+    let git_path = with_backend(|git| git.common_dir(Path::new(&current_path)))?
+        .to_string_lossy()
+        .to_string();
 
     // Return the parent directory of .git
     if git_path.ends_with(".git") {
@@ -141,249 +143,563 @@ pub async fn get_main_repo_path(current_path: String) -> Result<String, String>
     }
 }
 
-/// Create a new worktree with a new branch
+/// Create a new worktree. Without a `mode`, an existing local `branch_name` is checked
+/// out and anything else gets a new branch at `base_branch`; naming an existing branch
+/// together with a `base_branch` is an error.
 #[tauri::command]
 pub async fn create_worktree(
+    app_handle: AppHandle,
     repo_path: String,
     worktree_path: String,
-    branch_name: String,
-    base_branch: String,
-) -> Result<Worktree, String> {
+    branch_name: Option<String>,
+    base_branch: Option<String>,
+    mode: Option<CreateMode>,
+) -> Result<CreatedWorktree, String> {
+    let repo = Path::new(&repo_path);
+    let path = Path::new(&worktree_path);
+
     // Verify repo_path exists and is a git repository
-    if !Path::new(&repo_path).exists() {
+    if !repo.exists() {
         return Err(format!("Repository path does not exist: {}", repo_path));
     }
 
-    // Check if it's a git repository
-    let git_check = Command::new("git")
-        .args(["rev-parse", "--git-dir"])
-        .current_dir(&repo_path)
-        .output()
-        .map_err(|e| format!("Failed to check git repo: {}", e))?;
-
-    if !git_check.status.success() {
+    if with_backend(|git| git.common_dir(repo)).is_err() {
         return Err(format!("Not a git repository: {}", repo_path));
     }
 
+    let mode = match mode {
+        Some(mode) => mode,
+        None => {
+            let existing = branch_name.as_ref().filter(|branch| {
+                with_backend(|git| git.resolve_commit(repo, &format!("refs/heads/{}", branch)))
+                    .is_ok()
+            });
+            match existing {
+                Some(_) => CreateMode::Existing,
+                None => CreateMode::NewBranch,
+            }
+        }
+    };
+
+    // Neither mode starts from anything, so a base would be quietly ignored
+    if let Some(base) = &base_branch {
+        let branch = branch_name.as_deref().unwrap_or_default();
+        match mode {
+            CreateMode::Existing => {
+                return Err(format!(
+                    "Branch {} already exists, so it can't be created from {}",
+                    branch, base
+                ));
+            }
+            CreateMode::Orphan => {
+                return Err(format!(
+                    "Orphan branch {} has no history, so it can't be created from {}",
+                    branch, base
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    let require = |value: &Option<String>, what: &str| {
+        value
+            .clone()
+            .ok_or_else(|| format!("{} is required to create a {:?} worktree", what, mode))
+    };
+
     // Create parent directory for worktree if it doesn't exist
-    if let Some(parent) = Path::new(&worktree_path).parent() {
+    if let Some(parent) = path.parent() {
         if !parent.exists() {
             std::fs::create_dir_all(parent)
                 .map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
         }
     }
 
-    // Resolve the base reference to a commit hash to ensure it's valid
-    let resolve_output = Command::new("git")
-        .args(["rev-parse", "--verify", &base_branch])
-        .current_dir(&repo_path)
-        .output()
-        .map_err(|e| format!("Failed to resolve reference: {}", e))?;
+    let branch = match mode {
+        CreateMode::NewBranch => {
+            let branch = require(&branch_name, "A branch name")?;
+            let base = require(&base_branch, "A base branch")?;
 
-    let resolved_ref = if resolve_output.status.success() {
-        String::from_utf8_lossy(&resolve_output.stdout).trim().to_string()
-    } else {
-        // Fall back to HEAD if the reference is invalid
-        let head_output = Command::new("git")
-            .args(["rev-parse", "HEAD"])
-            .current_dir(&repo_path)
-            .output()
-            .map_err(|e| format!("Failed to get HEAD: {}", e))?;
-
-        if !head_output.status.success() {
-            let stderr = String::from_utf8_lossy(&head_output.stderr);
-            return Err(format!(
-                "Failed to resolve '{}' and HEAD in {}: {}",
-                base_branch, repo_path, stderr
-            ));
-        }
-        String::from_utf8_lossy(&head_output.stdout).trim().to_string()
-    };
+            // Resolve the base reference to a commit hash to ensure it's valid
+            let resolved_ref = branch::resolve_strict(repo, &base)?;
 
-    let output = Command::new("git")
-        .args([
-            "worktree",
-            "add",
-            "-b",
-            &branch_name,
-            &worktree_path,
-            &resolved_ref,
-        ])
-        .current_dir(&repo_path)
-        .output()
-        .map_err(|e| format!("Failed to execute git command: {}", e))?;
-
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
-    }
+            with_backend(|git| git.add_worktree(repo, path, &branch, &resolved_ref))?;
+
+            // Record where the new branch was forked from; the worktree exists either way
+            if let Err(e) = lineage::update(repo, |lineage| {
+                lineage.parents.insert(branch.clone(), base.clone())
+            }) {
+                eprintln!("Failed to record parent of {}: {}", branch, e);
+            }
 
-    // Get the HEAD of the new worktree
-    let head_output = Command::new("git")
-        .args(["rev-parse", "HEAD"])
-        .current_dir(&worktree_path)
-        .output()
-        .map_err(|e| format!("Failed to get HEAD: {}", e))?;
+            Some(branch)
+        }
+        CreateMode::Existing => {
+            let branch = require(&branch_name, "A branch name")?;
+            with_backend(|git| git.add_worktree_for_branch(repo, path, &branch))?;
+            Some(branch)
+        }
+        CreateMode::Track => {
+            let upstream = require(&base_branch, "A remote branch")?;
+            branch::resolve_strict(repo, &upstream)?;
+            // `origin/feature` is checked out as `feature` unless told otherwise
+            let branch = branch_name.clone().unwrap_or_else(|| {
+                upstream
+                    .split_once('/')
+                    .map_or(upstream.as_str(), |(_, name)| name)
+                    .to_string()
+            });
+            with_backend(|git| git.add_worktree_tracking(repo, path, &branch, &upstream))?;
+            Some(branch)
+        }
+        CreateMode::Orphan => {
+            let branch = require(&branch_name, "A branch name")?;
+            with_backend(|git| git.add_worktree_orphan(repo, path, &branch))?;
+            Some(branch)
+        }
+        CreateMode::Detached => {
+            let commit = require(&base_branch, "A revision")?;
+            let commit = branch::resolve_strict(repo, &commit)?;
+            with_backend(|git| git.add_worktree_detached(repo, path, &commit))?;
+            None
+        }
+    };
 
-    let head = String::from_utf8_lossy(&head_output.stdout)
-        .trim()
-        .to_string();
+    // Get the HEAD of the new worktree; an orphan branch has no commit yet
+    let head = match mode {
+        CreateMode::Orphan => "0".repeat(40),
+        _ => with_backend(|git| git.resolve_commit(path, "HEAD"))?,
+    };
 
-    Ok(Worktree {
-        path: worktree_path,
-        head,
-        branch: Some(branch_name),
-        is_bare: false,
-        is_detached: false,
-        is_main: false,
+    let name = with_backend(|git| git.git_dir(path))?
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string());
+
+    // Carried from the worktree that has the base checked out, or else the main one,
+    // and first so the provisioning recipe can rely on the files
+    let source = base_branch
+        .as_ref()
+        .and_then(|base| {
+            with_backend(|git| git.list_worktrees(repo))
+                .ok()?
+                .into_iter()
+                .find(|wt| wt.branch.as_ref() == Some(base) && wt.path != worktree_path)
+        })
+        .map_or_else(|| repo.to_path_buf(), |wt| PathBuf::from(wt.path));
+    let carried = carry::carry(&source, path).unwrap_or_else(|e| {
+        eprintln!("Failed to carry files into {}: {}", worktree_path, e);
+        Vec::new()
+    });
+
+    // Provisioning runs in the background; the worktree is usable either way
+    let provisioning = provision::start(app_handle, &worktree_path).unwrap_or_else(|e| {
+        eprintln!("Failed to provision {}: {}", worktree_path, e);
+        false
+    });
+
+    Ok(CreatedWorktree {
+        worktree: Worktree {
+            path: worktree_path,
+            head,
+            is_detached: branch.is_none(),
+            branch,
+            is_bare: false,
+            is_main: false,
+            name,
+            is_locked: false,
+            lock_reason: None,
+            is_prunable: false,
+            prunable_reason: None,
+        },
+        mode,
+        carried,
+        provisioning,
     })
 }
 
-/// Remove a worktree (with optional force)
+/// Remove a worktree, optionally deleting its branch.
+///
+/// `force` also removes a worktree with uncommitted changes, which are lost, so it
+/// fails listing them unless `discard_changes` confirms it. With `archive`, the branch
+/// tip is first kept under `refs/codecraft/archive/` so it can be restored; uncommitted
+/// changes are not. Deleting a branch that has commits its parent doesn't is refused,
+/// listing those commits, unless it is archived or `discard_unmerged` confirms it.
 #[tauri::command]
 pub async fn remove_worktree(
     repo_path: String,
     worktree_path: String,
     force: bool,
+    delete_branch: Option<bool>,
+    archive: Option<bool>,
+    discard_unmerged: Option<bool>,
+    discard_changes: Option<bool>,
+) -> Result<RemovedWorktree, String> {
+    let repo = Path::new(&repo_path);
+    let path = Path::new(&worktree_path);
+    let delete_branch = delete_branch.unwrap_or(false);
+    let archive = archive.unwrap_or(false);
+
+    // Without `force` git refuses a dirty worktree by itself
+    if force
+        && !discard_changes.unwrap_or(false)
+        && path.exists()
+        && with_backend(|git| git.is_dirty(path))?
+    {
+        let changed: Vec<String> = with_backend(|git| git.status(path, false))?
+            .into_iter()
+            .map(|file| file.path)
+            .collect();
+        return Err(format!(
+            "{} has uncommitted changes that removing it would discard: {}",
+            worktree_path,
+            changed.join(", ")
+        ));
+    }
+
+    let branch = with_backend(|git| git.list_worktrees(repo))?
+        .into_iter()
+        .find(|wt| Path::new(&wt.path) == path)
+        .and_then(|wt| wt.branch);
+    let mut removed = RemovedWorktree {
+        removed: false,
+        branch: branch.clone(),
+        unmerged: vec![],
+        archive_ref: None,
+        branch_deleted: false,
+    };
+
+    let branch = match branch {
+        Some(branch) => branch,
+        None if delete_branch || archive => {
+            return Err(format!("No branch is checked out in {}", worktree_path))
+        }
+        None => {
+            with_backend(|git| git.remove_worktree(repo, path, force))?;
+            removed.removed = true;
+            return Ok(removed);
+        }
+    };
+
+    if delete_branch && !archive && !discard_unmerged.unwrap_or(false) {
+        let parent = lineage::resolve_parent(repo, &branch)?.ok_or_else(|| {
+            format!(
+                "No parent branch known for {} to check it is merged; archive it instead",
+                branch
+            )
+        })?;
+        let revisions = [branch.clone(), format!("^{}", parent)];
+        removed.unmerged = with_backend(|git| git.log(repo, &revisions, MAX_UNMERGED_LISTED))?;
+        if !removed.unmerged.is_empty() {
+            return Ok(removed);
+        }
+    }
+
+    with_backend(|git| git.remove_worktree(repo, path, force))?;
+    removed.removed = true;
+
+    if archive {
+        removed.archive_ref = Some(branch::archive_branch(repo, &branch)?);
+    }
+
+    if delete_branch {
+        with_backend(|git| git.delete_branch(repo, &branch))?;
+        removed.branch_deleted = true;
+
+        if let Err(e) = lineage::update(repo, |lineage| lineage.parents.remove(&branch)) {
+            eprintln!("Failed to forget parent of {}: {}", branch, e);
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Lock a worktree so it is neither pruned nor removed, e.g. while it lives on a
+/// drive that isn't always mounted
+#[tauri::command]
+pub async fn lock_worktree(
+    repo_path: String,
+    worktree_path: String,
+    reason: Option<String>,
 ) -> Result<(), String> {
-    let mut args = vec!["worktree", "remove"];
-    if force {
-        args.push("--force");
+    let reason = reason.filter(|reason| !reason.trim().is_empty());
+    Ok(with_backend(|git| {
+        git.lock_worktree(
+            Path::new(&repo_path),
+            Path::new(&worktree_path),
+            reason.as_deref(),
+        )
+    })?)
+}
+
+#[tauri::command]
+pub async fn unlock_worktree(repo_path: String, worktree_path: String) -> Result<(), String> {
+    Ok(with_backend(|git| {
+        git.unlock_worktree(Path::new(&repo_path), Path::new(&worktree_path))
+    })?)
+}
+
+/// Move a linked worktree to `new_path`. Its `name` stays the same, so it keeps its
+/// identity in the graph. Returns the worktree as listed at its new location.
+#[tauri::command]
+pub async fn move_worktree(
+    repo_path: String,
+    worktree_path: String,
+    new_path: String,
+) -> Result<Worktree, String> {
+    let repo = Path::new(&repo_path);
+    let destination = Path::new(&new_path);
+
+    // The run's bookkeeping and its shell's working directory would be left behind
+    if provision::is_running(&worktree_path) {
+        return Err(format!(
+            "Provisioning is still running in {}; cancel it or wait for it to finish",
+            worktree_path
+        ));
+    }
+
+    if let Some(parent) = destination.parent() {
+        if !parent.exists() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
+        }
     }
-    args.push(&worktree_path);
 
-    let output = Command::new("git")
-        .args(&args)
-        .current_dir(&repo_path)
-        .output()
-        .map_err(|e| format!("Failed to execute git command: {}", e))?;
+    with_backend(|git| git.move_worktree(repo, Path::new(&worktree_path), destination))?;
+
+    // Listed paths are canonical, so compare against the canonical destination
+    let moved_path = destination
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve path: {}", e))?;
 
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    with_backend(|git| git.list_worktrees(repo))?
+        .into_iter()
+        .find(|wt| Path::new(&wt.path) == moved_path)
+        .ok_or_else(|| format!("Moved worktree not found at {}", new_path))
+}
+
+/// Rename the branch checked out in a worktree, keeping its place in the lineage
+#[tauri::command]
+pub async fn rename_worktree_branch(
+    worktree_path: String,
+    new_branch_name: String,
+) -> Result<(), String> {
+    let worktree = Path::new(&worktree_path);
+    let branch = with_backend(|git| git.list_worktrees(worktree))?
+        .into_iter()
+        .find(|wt| Path::new(&wt.path) == worktree)
+        .and_then(|wt| wt.branch)
+        .ok_or_else(|| format!("No branch is checked out in {}", worktree_path))?;
+
+    if branch == new_branch_name {
+        return Ok(());
     }
 
-    Ok(())
+    with_backend(|git| git.rename_branch(worktree, &branch, &new_branch_name))?;
+
+    lineage::update(worktree, |lineage| {
+        lineage.rename(&branch, &new_branch_name)
+    })
 }
 
-/// Merge a branch into another
+/// Forget worktrees whose directories were deleted. Returns the worktrees pruned, or
+/// with `dry_run` the ones that would be.
 #[tauri::command]
-pub async fn merge_branch(
+pub async fn prune_worktrees(
     repo_path: String,
-    source_branch: String,
-    target_branch: String,
+    dry_run: Option<bool>,
+) -> Result<Vec<Worktree>, String> {
+    let repo = Path::new(&repo_path);
+    let prunable: Vec<Worktree> = with_backend(|git| git.list_worktrees(repo))?
+        .into_iter()
+        .filter(|wt| wt.is_prunable)
+        .collect();
+
+    if !dry_run.unwrap_or(false) && !prunable.is_empty() {
+        with_backend(|git| git.prune_worktrees(repo))?;
+    }
+
+    Ok(prunable)
+}
+
+/// Fix the links between the repository and its worktrees after either was moved
+/// outside the app. `paths` are the new locations of moved linked worktrees.
+#[tauri::command]
+pub async fn repair_worktrees(repo_path: String, paths: Vec<String>) -> Result<(), String> {
+    Ok(with_backend(|git| {
+        git.repair_worktrees(Path::new(&repo_path), &paths)
+    })?)
+}
+
+/// A worktree checked out in the temp directory for the duration of an operation,
+/// removed again when dropped
+struct TempWorktree {
+    repo_path: PathBuf,
+    path: PathBuf,
+}
+
+impl TempWorktree {
+    fn checkout(repo_path: &Path, branch_name: &str) -> Result<Self, String> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let path = std::env::temp_dir().join(format!("codecraft-{}-{}", std::process::id(), nanos));
+
+        with_backend(|git| git.add_worktree_for_branch(repo_path, &path, branch_name))?;
+
+        Ok(TempWorktree {
+            repo_path: repo_path.to_path_buf(),
+            path,
+        })
+    }
+}
+
+impl Drop for TempWorktree {
+    fn drop(&mut self) {
+        if let Err(e) = with_backend(|git| git.remove_worktree(&self.repo_path, &self.path, true)) {
+            eprintln!(
+                "Failed to remove temporary worktree {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Find the worktree that has `branch_name` checked out
+fn find_checkout(repo_path: &Path, branch_name: &str) -> Result<Option<Worktree>, String> {
+    let worktrees = with_backend(|git| git.list_worktrees(repo_path))?;
+    Ok(worktrees
+        .into_iter()
+        .find(|wt| wt.branch.as_deref() == Some(branch_name)))
+}
+
+/// Run `op` in the worktree that has `branch_name` checked out, or in a temporary
+/// worktree on that branch. Failures in a temporary worktree are undone with `abort`
+/// so no working tree is left mid-operation.
+fn in_branch_worktree(
+    repo_path: &Path,
+    branch_name: &str,
+    op: impl Fn(&Path) -> Result<MergeResult, String>,
+    abort: impl Fn(&Path),
 ) -> Result<MergeResult, String> {
-    // First, checkout the target branch
-    let checkout_output = Command::new("git")
-        .args(["checkout", &target_branch])
-        .current_dir(&repo_path)
-        .output()
-        .map_err(|e| format!("Failed to checkout: {}", e))?;
-
-    if !checkout_output.status.success() {
-        return Ok(MergeResult {
-            success: false,
-            message: String::from_utf8_lossy(&checkout_output.stderr).to_string(),
-            conflicts: vec![],
-        });
+    if let Some(worktree) = find_checkout(repo_path, branch_name)? {
+        return op(Path::new(&worktree.path));
     }
 
-    // Then merge the source branch
-    let merge_output = Command::new("git")
-        .args(["merge", &source_branch, "--no-edit"])
-        .current_dir(&repo_path)
-        .output()
-        .map_err(|e| format!("Failed to merge: {}", e))?;
-
-    if merge_output.status.success() {
-        return Ok(MergeResult {
-            success: true,
-            message: "Merge successful".to_string(),
-            conflicts: vec![],
-        });
+    let temp = match TempWorktree::checkout(repo_path, branch_name) {
+        Ok(temp) => temp,
+        Err(e) => {
+            return Ok(MergeResult {
+                success: false,
+                message: e,
+                conflicts: vec![],
+                worktree_path: None,
+                commit: None,
+            })
+        }
+    };
+
+    let mut result = op(&temp.path)?;
+    result.worktree_path = None;
+
+    if !result.success {
+        abort(&temp.path);
+        result.message = format!(
+            "{}\nAborted because {} is not checked out in any worktree",
+            result.message, branch_name
+        )
+        .trim_start()
+        .to_string();
     }
 
-    // Check for conflicts
-    let status_output = Command::new("git")
-        .args(["status", "--porcelain"])
-        .current_dir(&repo_path)
-        .output()
-        .map_err(|e| format!("Failed to get status: {}", e))?;
-
-    let status = String::from_utf8_lossy(&status_output.stdout);
-    let conflicts: Vec<String> = status
-        .lines()
-        .filter(|line| line.starts_with("UU") || line.starts_with("AA") || line.starts_with("DD"))
-        .map(|line| line[3..].to_string())
-        .collect();
+    Ok(result)
+}
 
-    Ok(MergeResult {
-        success: false,
-        message: String::from_utf8_lossy(&merge_output.stderr).to_string(),
-        conflicts,
-    })
+/// Merge a branch into another using the chosen strategy (plain merge by default).
+///
+/// The merge runs in the worktree that already has the target checked out, or in a
+/// temporary worktree that is removed afterwards. The rebase strategy rebases the
+/// source the same way, in its own worktree, before fast-forwarding the target.
+#[tauri::command]
+pub async fn merge_branch(
+    repo_path: String,
+    source_branch: String,
+    target_branch: String,
+    strategy: Option<MergeStrategy>,
+) -> Result<MergeResult, String> {
+    let repo = Path::new(&repo_path);
+    let strategy = strategy.unwrap_or_default();
+
+    if strategy == MergeStrategy::Rebase {
+        let rebased = in_branch_worktree(
+            repo,
+            &source_branch,
+            |worktree| Ok(with_backend(|git| git.rebase(worktree, &target_branch))?),
+            |worktree| {
+                with_backend(|git| git.abort_rebase(worktree)).ok();
+            },
+        )?;
+
+        if !rebased.success {
+            return Ok(rebased);
+        }
+    }
+
+    in_branch_worktree(
+        repo,
+        &target_branch,
+        |worktree| {
+            Ok(with_backend(|git| {
+                git.merge(worktree, &source_branch, strategy)
+            })?)
+        },
+        |worktree| {
+            with_backend(|git| git.abort_merge(worktree)).ok();
+        },
+    )
 }
 
 /// Check for uncommitted changes in a worktree
 #[tauri::command]
 pub async fn has_uncommitted_changes(worktree_path: String) -> Result<bool, String> {
-    let output = Command::new("git")
-        .args(["status", "--porcelain"])
-        .current_dir(&worktree_path)
-        .output()
-        .map_err(|e| format!("Failed to execute git command: {}", e))?;
-
-    Ok(!output.stdout.is_empty())
+    Ok(with_backend(|git| git.is_dirty(Path::new(&worktree_path)))?)
 }
 
 /// Get branch info including upstream/parent relationship
 #[tauri::command]
 pub async fn get_branch_info(repo_path: String, branch_name: String) -> Result<BranchInfo, String> {
-    // Get upstream branch
-    let upstream_output = Command::new("git")
-        .args([
-            "config",
-            "--get",
-            &format!("branch.{}.remote", branch_name),
-        ])
-        .current_dir(&repo_path)
-        .output()
-        .ok();
-
-    let upstream = upstream_output
-        .filter(|o| o.status.success())
-        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
-
-    // Get ahead/behind counts
-    let rev_list_output = Command::new("git")
-        .args([
-            "rev-list",
-            "--left-right",
-            "--count",
-            &format!("{}...origin/{}", branch_name, branch_name),
-        ])
-        .current_dir(&repo_path)
-        .output()
-        .ok();
-
-    let (ahead, behind) = rev_list_output
-        .filter(|o| o.status.success())
-        .and_then(|o| {
-            let output = String::from_utf8_lossy(&o.stdout);
-            let parts: Vec<&str> = output.trim().split('\t').collect();
-            if parts.len() == 2 {
-                Some((
-                    parts[0].parse().unwrap_or(0),
-                    parts[1].parse().unwrap_or(0),
-                ))
-            } else {
-                None
-            }
-        })
-        .unwrap_or((0, 0));
+    let repo = Path::new(&repo_path);
+    let counts = |other: &str| {
+        with_backend(|git| git.ahead_behind(repo, &branch_name, other))
+            .map(|(ahead, behind)| (ahead as i32, behind as i32))
+            .unwrap_or((0, 0))
+    };
+
+    let upstream = with_backend(|git| git.upstream(repo, &branch_name))?;
+    let (ahead, behind) = match upstream {
+        Some(_) => counts(&format!("{}@{{upstream}}", branch_name)),
+        None => (0, 0),
+    };
+
+    let parent_branch = lineage::resolve_parent(repo, &branch_name)?;
+    let (parent_ahead, parent_behind) = match &parent_branch {
+        Some(parent) => counts(parent),
+        None => (0, 0),
+    };
+
+    // An unborn branch has no commits yet
+    let last_commit =
+        with_backend(|git| git.commit_info(repo, &format!("refs/heads/{}", branch_name))).ok();
 
     Ok(BranchInfo {
         name: branch_name,
         upstream,
         ahead,
         behind,
+        parent_branch,
+        parent_ahead,
+        parent_behind,
+        last_commit,
     })
 }
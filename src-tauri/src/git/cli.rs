@@ -0,0 +1,2073 @@
+use super::{GitBackend, GitError, GitResult, MergeTree, Transfer};
+use crate::commands::branch::Branch;
+use crate::commands::commit::CommitInfo;
+use crate::commands::diff::{DiffHunk, DiffLine, DiffLineKind, DiffTarget, FileDiff, FileStat};
+use crate::commands::divergence::Divergence;
+use crate::commands::status::{ConflictKind, FileChange, FileStatus, SubmoduleState};
+use crate::commands::worktree::{MergeResult, MergeStrategy, Worktree};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+
+/// Backend that shells out to the `git` executable on PATH
+pub struct CliBackend;
+
+/// Run git in `dir` and return its raw output, whether or not it succeeded
+pub fn git_output(dir: &Path, args: &[&str]) -> GitResult<Output> {
+    Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| GitError::Failed(format!("Failed to execute git command: {}", e)))
+}
+
+/// Run git in `dir` with `input` on stdin and return its stdout, or stderr as the error
+pub fn git_with_input(dir: &Path, args: &[&str], input: &str) -> GitResult<String> {
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| GitError::Failed(format!("Failed to execute git command: {}", e)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| GitError::Failed(format!("Failed to write to git: {}", e)))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| GitError::Failed(format!("Failed to execute git command: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(GitError::Failed(failure_message(&output)));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Run a git command that talks to a remote, passing each progress line to `transfer`
+/// as it arrives. Git redraws progress in place with `\r`; only lines it finishes with
+/// `\n` are kept in the returned stderr.
+fn git_transfer(dir: &Path, args: &[&str], transfer: &Transfer) -> GitResult<Output> {
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .envs(transfer.env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| GitError::Failed(format!("Failed to execute git command: {}", e)))?;
+
+    // Drain stdout alongside stderr so neither pipe can fill up and stall git
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let stdout_reader = thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = stdout.read_to_end(&mut buffer);
+        buffer
+    });
+
+    let mut stderr = Vec::new();
+    let mut line = Vec::new();
+    let source = BufReader::new(child.stderr.take().expect("stderr is piped"));
+    for byte in source.bytes() {
+        let Ok(byte) = byte else { break };
+        if byte != b'\r' && byte != b'\n' {
+            line.push(byte);
+            continue;
+        }
+        if !line.is_empty() {
+            (transfer.on_progress)(&String::from_utf8_lossy(&line));
+        }
+        if byte == b'\n' {
+            stderr.append(&mut line);
+            stderr.push(b'\n');
+        }
+        line.clear();
+    }
+    stderr.append(&mut line);
+
+    let status = child
+        .wait()
+        .map_err(|e| GitError::Failed(format!("Failed to execute git command: {}", e)))?;
+    let stdout = stdout_reader.join().unwrap_or_default();
+
+    Ok(Output {
+        status,
+        stdout,
+        stderr,
+    })
+}
+
+/// Run git in `dir` and return its stdout untouched, or stderr as the error
+pub fn git_raw(dir: &Path, args: &[&str]) -> GitResult<String> {
+    let output = git_output(dir, args)?;
+
+    if !output.status.success() {
+        return Err(GitError::Failed(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Run git in `dir` and return its stdout without trailing whitespace, or stderr as the error
+pub fn git(dir: &Path, args: &[&str]) -> GitResult<String> {
+    git_raw(dir, args).map(|stdout| stdout.trim_end().to_string())
+}
+
+/// `git log` format for the fields of `CommitInfo`, separated by NULs
+const COMMIT_FORMAT: &str = "%H%x00%P%x00%s%x00%b%x00%an%x00%ae%x00%at%x00%ct";
+
+fn parse_commit(record: &str) -> GitResult<CommitInfo> {
+    let fields: Vec<&str> = record.split('\0').collect();
+    let [sha, parents, summary, body, author_name, author_email, author_time, commit_time] =
+        fields.as_slice()
+    else {
+        return Err(GitError::Failed(format!(
+            "Unexpected git log output: {}",
+            record
+        )));
+    };
+
+    Ok(CommitInfo {
+        sha: sha.to_string(),
+        parents: parents.split_whitespace().map(str::to_string).collect(),
+        summary: summary.to_string(),
+        body: body.trim_end().to_string(),
+        author_name: author_name.to_string(),
+        author_email: author_email.to_string(),
+        author_time: author_time.trim().parse().unwrap_or(0),
+        commit_time: commit_time.trim().parse().unwrap_or(0),
+    })
+}
+
+/// `args` followed by `--` and `paths`, so paths are never taken for options or revisions
+fn with_paths<'a>(args: &[&'a str], paths: &'a [String]) -> Vec<&'a str> {
+    let mut args = args.to_vec();
+    args.push("--");
+    args.extend(paths.iter().map(String::as_str));
+    args
+}
+
+/// Explain a failed command: git reports some failures (like merge conflicts) on stdout
+fn failure_message(output: &Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.trim().is_empty() {
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    } else {
+        stderr.trim().to_string()
+    }
+}
+
+/// Paths left unmerged by a merge or rebase
+fn conflicted_paths(worktree_path: &Path) -> GitResult<Vec<String>> {
+    let status = git_raw(worktree_path, &["status", "--porcelain=v2", "-z"])?;
+    Ok(parse_status_v2(&status)
+        .into_iter()
+        .filter(|entry| entry.conflict.is_some())
+        .map(|entry| entry.path)
+        .collect())
+}
+
+/// Result for a merge or rebase that stopped, listing whatever it left conflicted
+fn conflict_result(worktree_path: &Path, output: &Output) -> GitResult<MergeResult> {
+    Ok(MergeResult {
+        success: false,
+        message: failure_message(output),
+        conflicts: conflicted_paths(worktree_path)?,
+        worktree_path: Some(worktree_path.to_string_lossy().to_string()),
+        commit: None,
+    })
+}
+
+/// Run a rebase or cherry-pick step and report where it stopped, if it did
+fn run_sequencer(worktree_path: &Path, args: &[&str], done: &str) -> GitResult<MergeResult> {
+    // Keep the existing messages instead of opening an editor with no terminal to show
+    // it. `GIT_EDITOR` wins over `core.editor` and any `VISUAL` or `EDITOR` inherited.
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(worktree_path)
+        .env("GIT_EDITOR", "true")
+        .output()
+        .map_err(|e| GitError::Failed(format!("Failed to execute git command: {}", e)))?;
+    if !output.status.success() {
+        return conflict_result(worktree_path, &output);
+    }
+
+    Ok(MergeResult {
+        success: true,
+        message: done.to_string(),
+        conflicts: vec![],
+        worktree_path: Some(worktree_path.to_string_lossy().to_string()),
+        commit: Some(git(worktree_path, &["rev-parse", "HEAD"])?),
+    })
+}
+
+/// Commit message for a squash merge: a subject plus the subjects being squashed
+fn squash_message(worktree_path: &Path, source_branch: &str) -> GitResult<String> {
+    let subjects = git(
+        worktree_path,
+        &[
+            "log",
+            "--reverse",
+            "--format=* %s",
+            &format!("HEAD..{}", source_branch),
+        ],
+    )?;
+
+    Ok(format!(
+        "Squash merge branch '{}'\n\n{}",
+        source_branch, subjects
+    ))
+}
+
+fn parse_change(code: char) -> Option<FileChange> {
+    match code {
+        'A' => Some(FileChange::Added),
+        'M' => Some(FileChange::Modified),
+        'D' => Some(FileChange::Deleted),
+        'R' => Some(FileChange::Renamed),
+        'C' => Some(FileChange::Copied),
+        'T' => Some(FileChange::TypeChanged),
+        _ => None,
+    }
+}
+
+fn parse_conflict(xy: &str) -> Option<ConflictKind> {
+    match xy {
+        "UU" => Some(ConflictKind::BothModified),
+        "AA" => Some(ConflictKind::BothAdded),
+        "DD" => Some(ConflictKind::BothDeleted),
+        "AU" => Some(ConflictKind::AddedByUs),
+        "UA" => Some(ConflictKind::AddedByThem),
+        "DU" => Some(ConflictKind::DeletedByUs),
+        "UD" => Some(ConflictKind::DeletedByThem),
+        _ => None,
+    }
+}
+
+/// Kind of conflict from which of stages 1 (base), 2 (ours) and 3 (theirs) an
+/// unmerged path has
+fn stages_conflict(stages: [bool; 3]) -> ConflictKind {
+    match stages {
+        [true, true, true] => ConflictKind::BothModified,
+        [false, true, true] => ConflictKind::BothAdded,
+        [true, true, false] => ConflictKind::DeletedByThem,
+        [true, false, true] => ConflictKind::DeletedByUs,
+        [false, true, false] => ConflictKind::AddedByUs,
+        [false, false, true] => ConflictKind::AddedByThem,
+        _ => ConflictKind::BothDeleted,
+    }
+}
+
+/// Parse `git merge-tree --write-tree -z` output: "<tree>\0", then
+/// "<mode> <object> <stage>\t<path>\0" per unmerged entry and an empty field,
+/// then "<count>\0<path>\0...<type>\0<message>\0" per message
+fn parse_merge_tree(output: &str) -> MergeTree {
+    let mut fields = output.split('\0');
+    let tree = fields.next().unwrap_or_default().to_string();
+
+    let mut unmerged: Vec<(String, [bool; 3])> = Vec::new();
+    for entry in fields.by_ref() {
+        let Some((info, path)) = entry.split_once('\t') else {
+            break;
+        };
+        let Some(stage) = info
+            .rsplit(' ')
+            .next()
+            .and_then(|stage| stage.parse::<usize>().ok())
+            .filter(|stage| (1..=3).contains(stage))
+        else {
+            continue;
+        };
+        // Entries of one path are listed together, by stage
+        match unmerged.last_mut() {
+            Some((last, stages)) if last == path => stages[stage - 1] = true,
+            _ => {
+                let mut stages = [false; 3];
+                stages[stage - 1] = true;
+                unmerged.push((path.to_string(), stages));
+            }
+        }
+    }
+
+    let mut messages = Vec::new();
+    while let Some(count) = fields.next().and_then(|count| count.parse::<usize>().ok()) {
+        // Skip the paths and the type
+        let Some(message) = fields.nth(count + 1) else {
+            break;
+        };
+        messages.push(message.trim_end().to_string());
+    }
+
+    MergeTree {
+        tree,
+        conflicts: unmerged
+            .into_iter()
+            .map(|(path, stages)| (path, stages_conflict(stages)))
+            .collect(),
+        messages,
+    }
+}
+
+/// Parse the `<sub>` field: `N...` for plain files, `S<c><m><u>` for submodules
+fn parse_submodule(sub: &str) -> Option<SubmoduleState> {
+    let flags: Vec<char> = sub.strip_prefix('S')?.chars().collect();
+    Some(SubmoduleState {
+        commit_changed: flags.first() == Some(&'C'),
+        has_tracked_changes: flags.get(1) == Some(&'M'),
+        has_untracked_changes: flags.get(2) == Some(&'U'),
+    })
+}
+
+fn unchanged_entry(path: &str) -> FileStatus {
+    FileStatus {
+        path: path.to_string(),
+        original_path: None,
+        staged: None,
+        unstaged: None,
+        untracked: false,
+        ignored: false,
+        conflict: None,
+        submodule: None,
+    }
+}
+
+/// Parse `git status --porcelain=v2 -z` output
+fn parse_status_v2(output: &str) -> Vec<FileStatus> {
+    let mut entries = Vec::new();
+    let mut records = output.split('\0').filter(|record| !record.is_empty());
+
+    while let Some(record) = records.next() {
+        let (kind, rest) = record.split_at(1);
+        let rest = rest.strip_prefix(' ').unwrap_or(rest);
+        match kind {
+            "1" | "2" => {
+                // XY sub mH mI mW hH hI [score] path
+                let field_count = if kind == "1" { 8 } else { 9 };
+                let fields: Vec<&str> = rest.splitn(field_count, ' ').collect();
+                let (Some(xy), Some(sub), Some(path)) =
+                    (fields.first(), fields.get(1), fields.get(field_count - 1))
+                else {
+                    continue;
+                };
+
+                let mut xy = xy.chars();
+                let mut entry = unchanged_entry(path);
+                entry.staged = xy.next().and_then(parse_change);
+                entry.unstaged = xy.next().and_then(parse_change);
+                entry.submodule = parse_submodule(sub);
+                if kind == "2" {
+                    // With -z the original path is the next record
+                    entry.original_path = records.next().map(str::to_string);
+                }
+                entries.push(entry);
+            }
+            "u" => {
+                // XY sub m1 m2 m3 mW h1 h2 h3 path
+                let fields: Vec<&str> = rest.splitn(10, ' ').collect();
+                let (Some(xy), Some(sub), Some(path)) =
+                    (fields.first(), fields.get(1), fields.get(9))
+                else {
+                    continue;
+                };
+
+                let mut entry = unchanged_entry(path);
+                entry.conflict = parse_conflict(xy);
+                entry.submodule = parse_submodule(sub);
+                entries.push(entry);
+            }
+            "?" => entries.push(FileStatus {
+                untracked: true,
+                ..unchanged_entry(rest)
+            }),
+            "!" => entries.push(FileStatus {
+                ignored: true,
+                ..unchanged_entry(rest)
+            }),
+            // Headers (`#`) and anything newer than this parser
+            _ => {}
+        }
+    }
+
+    entries
+}
+
+/// Parse `git worktree list --porcelain` output
+fn parse_worktree_list(output: &str) -> Vec<Worktree> {
+    let mut worktrees = Vec::new();
+    let mut current_path = String::new();
+    let mut current_head = String::new();
+    let mut current_branch: Option<String> = None;
+    let mut is_bare = false;
+    let mut is_detached = false;
+    let mut lock_reason: Option<String> = None;
+    let mut is_locked = false;
+    let mut prunable_reason: Option<String> = None;
+    let mut is_first = true;
+
+    for line in output.lines() {
+        if line.is_empty() {
+            if !current_path.is_empty() {
+                worktrees.push(Worktree {
+                    path: current_path.clone(),
+                    head: current_head.clone(),
+                    branch: current_branch.clone(),
+                    is_bare,
+                    is_detached,
+                    is_main: is_first,
+                    name: None,
+                    is_locked,
+                    lock_reason: lock_reason.take(),
+                    is_prunable: prunable_reason.is_some(),
+                    prunable_reason: prunable_reason.take(),
+                });
+                is_first = false;
+            }
+            current_path.clear();
+            current_head.clear();
+            current_branch = None;
+            is_bare = false;
+            is_detached = false;
+            is_locked = false;
+            lock_reason = None;
+            prunable_reason = None;
+            continue;
+        }
+
+        if let Some(path) = line.strip_prefix("worktree ") {
+            current_path = path.to_string();
+        } else if let Some(head) = line.strip_prefix("HEAD ") {
+            current_head = head.to_string();
+        } else if let Some(branch) = line.strip_prefix("branch ") {
+            current_branch = Some(branch.replace("refs/heads/", ""));
+        } else if line == "bare" {
+            is_bare = true;
+        } else if line == "detached" {
+            is_detached = true;
+        } else if let Some(lock) = line.strip_prefix("locked") {
+            // "locked" alone, or followed by the reason
+            is_locked = true;
+            lock_reason = lock.strip_prefix(' ').map(str::to_string);
+        } else if let Some(reason) = line.strip_prefix("prunable ") {
+            prunable_reason = Some(reason.to_string());
+        }
+    }
+
+    // Handle last worktree if output doesn't end with empty line
+    if !current_path.is_empty() {
+        worktrees.push(Worktree {
+            path: current_path,
+            head: current_head,
+            branch: current_branch,
+            is_bare,
+            is_detached,
+            is_main: is_first,
+            name: None,
+            is_prunable: prunable_reason.is_some(),
+            is_locked,
+            lock_reason,
+            prunable_reason,
+        });
+    }
+
+    worktrees
+}
+
+/// Options that keep `git diff` output in the plain form the parsers below expect,
+/// whatever the user's diff configuration
+const DIFF_OPTIONS: [&str; 7] = [
+    "--no-color",
+    "--no-ext-diff",
+    "--no-textconv",
+    "--no-relative",
+    "--no-abbrev",
+    "--submodule=short",
+    "-M",
+];
+
+/// Parse a hunk range like `12,3`; a missing count means one line
+fn parse_range(range: &str) -> Option<(u32, u32)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// Hunks of one file's part of `git diff -p` output
+fn parse_hunks(section: &str) -> Vec<DiffHunk> {
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+    let (mut old_line, mut new_line) = (0, 0);
+
+    for line in section.split_inclusive('\n') {
+        if let Some(ranges) = line.strip_prefix("@@ ") {
+            // "@@ -<old start>,<count> +<new start>,<count> @@ <context>"
+            let mut ranges = ranges.split(' ');
+            let old = ranges
+                .next()
+                .and_then(|r| parse_range(r.strip_prefix('-')?));
+            let new = ranges
+                .next()
+                .and_then(|r| parse_range(r.strip_prefix('+')?));
+            let (Some((old_start, old_lines)), Some((new_start, new_lines))) = (old, new) else {
+                continue;
+            };
+            (old_line, new_line) = (old_start, new_start);
+            hunks.push(DiffHunk {
+                header: line.trim_end().to_string(),
+                old_start,
+                old_lines,
+                new_start,
+                new_lines,
+                lines: vec![],
+            });
+            continue;
+        }
+
+        // Lines before the first hunk are the file's headers
+        let Some(hunk) = hunks.last_mut() else {
+            continue;
+        };
+        let content = line.get(1..).unwrap_or_default().to_string();
+        let (kind, old, new) = match line.as_bytes().first() {
+            Some(b' ') => (DiffLineKind::Context, Some(old_line), Some(new_line)),
+            Some(b'+') => (DiffLineKind::Added, None, Some(new_line)),
+            Some(b'-') => (DiffLineKind::Removed, Some(old_line), None),
+            // "\ No newline at end of file": the line before really ends without one
+            Some(b'\\') => {
+                if let Some(last) = hunk.lines.last_mut() {
+                    last.content
+                        .truncate(last.content.trim_end_matches('\n').len());
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        old_line += old.is_some() as u32;
+        new_line += new.is_some() as u32;
+        hunk.lines.push(DiffLine {
+            kind,
+            content,
+            old_line: old,
+            new_line: new,
+        });
+    }
+
+    hunks
+}
+
+/// Content of a blob, unless `object` is the null id of a missing side
+fn read_blob(dir: &Path, object: &str) -> Option<String> {
+    if object.trim_start_matches('0').is_empty() {
+        return None;
+    }
+    let output = git_output(dir, &["cat-file", "blob", object]).ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn read_worktree_file(worktree_path: &Path, path: &str) -> Option<String> {
+    fs::read(worktree_path.join(path))
+        .ok()
+        .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+}
+
+/// Diff of an untracked file: all of it is added
+fn untracked_diff(worktree_path: &Path, path: &str, include_content: bool) -> FileDiff {
+    let bytes = fs::read(worktree_path.join(path)).unwrap_or_default();
+    let is_binary = bytes.contains(&0);
+    let content = String::from_utf8_lossy(&bytes).to_string();
+
+    let mut hunks = Vec::new();
+    if !is_binary && !content.is_empty() {
+        let lines: Vec<DiffLine> = content
+            .split_inclusive('\n')
+            .zip(1..)
+            .map(|(line, number)| DiffLine {
+                kind: DiffLineKind::Added,
+                content: line.to_string(),
+                old_line: None,
+                new_line: Some(number),
+            })
+            .collect();
+        let count = lines.len() as u32;
+        hunks.push(DiffHunk {
+            header: match count {
+                1 => "@@ -0,0 +1 @@".to_string(),
+                _ => format!("@@ -0,0 +1,{} @@", count),
+            },
+            old_start: 0,
+            old_lines: 0,
+            new_start: 1,
+            new_lines: count,
+            lines,
+        });
+    }
+
+    FileDiff {
+        old_path: None,
+        new_path: Some(path.to_string()),
+        change: FileChange::Added,
+        is_binary,
+        old_blob: None,
+        new_blob: None,
+        old_content: None,
+        new_content: (include_content && !is_binary).then_some(content),
+        hunks,
+    }
+}
+
+impl GitBackend for CliBackend {
+    fn name(&self) -> &'static str {
+        "cli"
+    }
+
+    fn list_worktrees(&self, repo_path: &Path) -> GitResult<Vec<Worktree>> {
+        let stdout = git(repo_path, &["worktree", "list", "--porcelain"])?;
+        let mut worktrees = parse_worktree_list(&stdout);
+
+        // The porcelain output doesn't name linked worktrees; the `gitdir` file in each
+        // administrative directory points back at the worktree's `.git`
+        let admin_dir = self.common_dir(repo_path)?.join("worktrees");
+        let names: HashMap<PathBuf, String> = fs::read_dir(admin_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let gitdir = fs::read_to_string(entry.path().join("gitdir")).ok()?;
+                let path = Path::new(gitdir.trim_end()).parent()?.to_path_buf();
+                Some((path, entry.file_name().to_string_lossy().to_string()))
+            })
+            .collect();
+        for worktree in worktrees.iter_mut().filter(|wt| !wt.is_main) {
+            worktree.name = names.get(Path::new(&worktree.path)).cloned();
+        }
+
+        Ok(worktrees)
+    }
+
+    fn common_dir(&self, path: &Path) -> GitResult<PathBuf> {
+        let git_common = git(path, &["rev-parse", "--git-common-dir"])?;
+
+        if Path::new(&git_common).is_absolute() {
+            Ok(PathBuf::from(git_common))
+        } else {
+            path.join(&git_common)
+                .canonicalize()
+                .map_err(|e| GitError::Failed(format!("Failed to resolve path: {}", e)))
+        }
+    }
+
+    fn git_dir(&self, path: &Path) -> GitResult<PathBuf> {
+        let git_dir = git(path, &["rev-parse", "--absolute-git-dir"])?;
+        PathBuf::from(git_dir)
+            .canonicalize()
+            .map_err(|e| GitError::Failed(format!("Failed to resolve path: {}", e)))
+    }
+
+    fn resolve_commit(&self, repo_path: &Path, rev: &str) -> GitResult<String> {
+        git(
+            repo_path,
+            &[
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("{}^{{commit}}", rev),
+            ],
+        )
+        .map_err(|_| GitError::Failed(format!("Unknown revision: {}", rev)))
+    }
+
+    fn add_worktree(
+        &self,
+        repo_path: &Path,
+        worktree_path: &Path,
+        branch_name: &str,
+        start_point: &str,
+    ) -> GitResult<()> {
+        let worktree_path = worktree_path.to_string_lossy();
+        git(
+            repo_path,
+            &[
+                "worktree",
+                "add",
+                "-b",
+                branch_name,
+                &worktree_path,
+                start_point,
+            ],
+        )
+        .map(|_| ())
+    }
+
+    fn remove_worktree(
+        &self,
+        repo_path: &Path,
+        worktree_path: &Path,
+        force: bool,
+    ) -> GitResult<()> {
+        let worktree_path = worktree_path.to_string_lossy();
+        let mut args = vec!["worktree", "remove"];
+        if force {
+            args.push("--force");
+        }
+        args.push(&worktree_path);
+
+        git(repo_path, &args).map(|_| ())
+    }
+
+    fn add_worktree_for_branch(
+        &self,
+        repo_path: &Path,
+        worktree_path: &Path,
+        branch_name: &str,
+    ) -> GitResult<()> {
+        let worktree_path = worktree_path.to_string_lossy();
+        git(repo_path, &["worktree", "add", &worktree_path, branch_name]).map(|_| ())
+    }
+
+    fn add_worktree_tracking(
+        &self,
+        repo_path: &Path,
+        worktree_path: &Path,
+        branch_name: &str,
+        upstream: &str,
+    ) -> GitResult<()> {
+        let worktree_path = worktree_path.to_string_lossy();
+        git(
+            repo_path,
+            &[
+                "worktree",
+                "add",
+                "--track",
+                "-b",
+                branch_name,
+                &worktree_path,
+                upstream,
+            ],
+        )
+        .map(|_| ())
+    }
+
+    fn add_worktree_orphan(
+        &self,
+        repo_path: &Path,
+        worktree_path: &Path,
+        branch_name: &str,
+    ) -> GitResult<()> {
+        let branch_ref = format!("refs/heads/{}", branch_name);
+        git(repo_path, &["check-ref-format", &branch_ref])
+            .map_err(|_| GitError::Failed(format!("Invalid branch name: {}", branch_name)))?;
+        if git(
+            repo_path,
+            &["rev-parse", "--verify", "--quiet", &branch_ref],
+        )
+        .is_ok()
+        {
+            return Err(GitError::Failed(format!(
+                "A branch named '{}' already exists",
+                branch_name
+            )));
+        }
+
+        // `worktree add --orphan` needs git 2.42; an unchecked-out worktree pointed at an
+        // unborn branch is the same thing
+        let path = worktree_path.to_string_lossy();
+        git(
+            repo_path,
+            &["worktree", "add", "--no-checkout", "--detach", &path],
+        )?;
+        git(worktree_path, &["symbolic-ref", "HEAD", &branch_ref]).map(|_| ())
+    }
+
+    fn add_worktree_detached(
+        &self,
+        repo_path: &Path,
+        worktree_path: &Path,
+        commit: &str,
+    ) -> GitResult<()> {
+        let worktree_path = worktree_path.to_string_lossy();
+        git(
+            repo_path,
+            &["worktree", "add", "--detach", &worktree_path, commit],
+        )
+        .map(|_| ())
+    }
+
+    fn lock_worktree(
+        &self,
+        repo_path: &Path,
+        worktree_path: &Path,
+        reason: Option<&str>,
+    ) -> GitResult<()> {
+        let worktree_path = worktree_path.to_string_lossy();
+        let mut args = vec!["worktree", "lock"];
+        if let Some(reason) = reason {
+            args.extend(["--reason", reason]);
+        }
+        args.push(&worktree_path);
+
+        git(repo_path, &args).map(|_| ())
+    }
+
+    fn unlock_worktree(&self, repo_path: &Path, worktree_path: &Path) -> GitResult<()> {
+        let worktree_path = worktree_path.to_string_lossy();
+        git(repo_path, &["worktree", "unlock", &worktree_path]).map(|_| ())
+    }
+
+    fn move_worktree(
+        &self,
+        repo_path: &Path,
+        worktree_path: &Path,
+        new_path: &Path,
+    ) -> GitResult<()> {
+        let worktree_path = worktree_path.to_string_lossy();
+        let new_path = new_path.to_string_lossy();
+        git(repo_path, &["worktree", "move", &worktree_path, &new_path]).map(|_| ())
+    }
+
+    fn prune_worktrees(&self, repo_path: &Path) -> GitResult<()> {
+        git(repo_path, &["worktree", "prune"]).map(|_| ())
+    }
+
+    fn repair_worktrees(&self, repo_path: &Path, paths: &[String]) -> GitResult<()> {
+        let mut args = vec!["worktree", "repair"];
+        args.extend(paths.iter().map(String::as_str));
+        git(repo_path, &args).map(|_| ())
+    }
+
+    fn merge(
+        &self,
+        worktree_path: &Path,
+        source_branch: &str,
+        strategy: MergeStrategy,
+    ) -> GitResult<MergeResult> {
+        let args: &[&str] = match strategy {
+            MergeStrategy::Merge => &["merge", "--no-edit"],
+            MergeStrategy::NoFf => &["merge", "--no-ff", "--no-edit"],
+            // Rebase merges have already rebased the source, so only a fast-forward remains
+            MergeStrategy::FfOnly | MergeStrategy::Rebase => &["merge", "--ff-only"],
+            MergeStrategy::Squash => &["merge", "--squash"],
+        };
+        let mut args = args.to_vec();
+        args.push(source_branch);
+
+        // Build the squash message before the merge moves anything
+        let squash_message = match strategy {
+            MergeStrategy::Squash => Some(squash_message(worktree_path, source_branch)?),
+            _ => None,
+        };
+
+        let merge_output = git_output(worktree_path, &args)?;
+        if !merge_output.status.success() {
+            return conflict_result(worktree_path, &merge_output);
+        }
+
+        // `merge --squash` only stages the changes; commit them unless there is nothing new
+        if let Some(message) = squash_message {
+            let staged = git_output(worktree_path, &["diff", "--cached", "--quiet"])?;
+            if !staged.status.success() {
+                git(worktree_path, &["commit", "-m", &message])?;
+            }
+        }
+
+        Ok(MergeResult {
+            success: true,
+            message: "Merge successful".to_string(),
+            conflicts: vec![],
+            worktree_path: Some(worktree_path.to_string_lossy().to_string()),
+            commit: Some(git(worktree_path, &["rev-parse", "HEAD"])?),
+        })
+    }
+
+    fn abort_merge(&self, worktree_path: &Path) -> GitResult<()> {
+        // A conflicted `merge --squash` leaves no MERGE_HEAD, so `merge --abort` can't undo it
+        git(worktree_path, &["merge", "--abort"])
+            .or_else(|_| git(worktree_path, &["reset", "--merge"]))
+            .map(|_| ())
+    }
+
+    fn merge_tree(&self, repo_path: &Path, ours: &str, theirs: &str) -> GitResult<MergeTree> {
+        let output = git_output(
+            repo_path,
+            &["merge-tree", "--write-tree", "-z", ours, theirs],
+        )?;
+        // 1 means the merge has conflicts; anything else but 0 means it couldn't be done
+        if !matches!(output.status.code(), Some(0 | 1)) {
+            return Err(GitError::Failed(failure_message(&output)));
+        }
+
+        Ok(parse_merge_tree(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    fn rebase(&self, worktree_path: &Path, upstream: &str) -> GitResult<MergeResult> {
+        run_sequencer(worktree_path, &["rebase", upstream], "Rebase successful")
+    }
+
+    fn rebase_onto(
+        &self,
+        worktree_path: &Path,
+        onto: &str,
+        upstream: &str,
+    ) -> GitResult<MergeResult> {
+        run_sequencer(
+            worktree_path,
+            &["rebase", "--onto", onto, upstream],
+            "Rebase successful",
+        )
+    }
+
+    fn continue_rebase(&self, worktree_path: &Path) -> GitResult<MergeResult> {
+        run_sequencer(
+            worktree_path,
+            &["rebase", "--continue"],
+            "Rebase successful",
+        )
+    }
+
+    fn skip_rebase(&self, worktree_path: &Path) -> GitResult<MergeResult> {
+        run_sequencer(worktree_path, &["rebase", "--skip"], "Rebase successful")
+    }
+
+    fn abort_rebase(&self, worktree_path: &Path) -> GitResult<()> {
+        git(worktree_path, &["rebase", "--abort"]).map(|_| ())
+    }
+
+    fn cherry_pick(
+        &self,
+        worktree_path: &Path,
+        commits: &[String],
+        record_origin: bool,
+    ) -> GitResult<MergeResult> {
+        let mut args = vec!["cherry-pick"];
+        if record_origin {
+            args.push("-x");
+        }
+        args.extend(commits.iter().map(String::as_str));
+
+        run_sequencer(worktree_path, &args, "Cherry-pick successful")
+    }
+
+    fn continue_cherry_pick(&self, worktree_path: &Path) -> GitResult<MergeResult> {
+        run_sequencer(
+            worktree_path,
+            &["cherry-pick", "--continue"],
+            "Cherry-pick successful",
+        )
+    }
+
+    fn skip_cherry_pick(&self, worktree_path: &Path) -> GitResult<MergeResult> {
+        run_sequencer(
+            worktree_path,
+            &["cherry-pick", "--skip"],
+            "Cherry-pick successful",
+        )
+    }
+
+    fn abort_cherry_pick(&self, worktree_path: &Path) -> GitResult<()> {
+        git(worktree_path, &["cherry-pick", "--abort"]).map(|_| ())
+    }
+
+    fn status(&self, worktree_path: &Path, include_ignored: bool) -> GitResult<Vec<FileStatus>> {
+        let mut args = vec!["status", "--porcelain=v2", "-z", "--untracked-files=all"];
+        if include_ignored {
+            args.push("--ignored");
+        }
+
+        Ok(parse_status_v2(&git_raw(worktree_path, &args)?))
+    }
+
+    fn untracked_matching(
+        &self,
+        worktree_path: &Path,
+        patterns: &[String],
+    ) -> GitResult<Vec<String>> {
+        if patterns.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // `glob` magic gives the patterns gitignore-like semantics: `*` stops at `/`
+        let pathspecs: Vec<String> = patterns
+            .iter()
+            .map(|pattern| format!(":(glob){}", pattern))
+            .collect();
+        let output = git_raw(
+            worktree_path,
+            &with_paths(&["ls-files", "--others", "--directory", "-z"], &pathspecs),
+        )?;
+
+        Ok(output
+            .split('\0')
+            .filter(|path| !path.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn conflict_stage(
+        &self,
+        worktree_path: &Path,
+        path: &str,
+        stage: u8,
+    ) -> GitResult<Option<Vec<u8>>> {
+        // Entries look like "<mode> <object> <stage>\t<path>"
+        let entries = git_raw(worktree_path, &["ls-files", "-s", "-z", "--", path])?;
+        let stage = stage.to_string();
+        let Some(object) = entries.split('\0').find_map(|entry| {
+            let (info, _) = entry.split_once('\t')?;
+            let mut fields = info.split(' ');
+            let object = fields.nth(1)?;
+            (fields.next()? == stage).then(|| object.to_string())
+        }) else {
+            return Ok(None);
+        };
+
+        let output = git_output(worktree_path, &["cat-file", "blob", &object])?;
+        if !output.status.success() {
+            return Err(GitError::Failed(failure_message(&output)));
+        }
+        Ok(Some(output.stdout))
+    }
+
+    fn stage(&self, worktree_path: &Path, paths: &[String]) -> GitResult<()> {
+        git(worktree_path, &with_paths(&["add", "-A"], paths)).map(|_| ())
+    }
+
+    fn unstage(&self, worktree_path: &Path, paths: &[String]) -> GitResult<()> {
+        git(worktree_path, &with_paths(&["reset", "-q"], paths)).map(|_| ())
+    }
+
+    fn intent_to_add(&self, worktree_path: &Path, paths: &[String]) -> GitResult<()> {
+        git(
+            worktree_path,
+            &with_paths(&["add", "--intent-to-add"], paths),
+        )
+        .map(|_| ())
+    }
+
+    fn apply_to_index(&self, worktree_path: &Path, patch: &str, reverse: bool) -> GitResult<()> {
+        let mut args = vec!["apply", "--cached", "--recount", "--whitespace=nowarn"];
+        if reverse {
+            args.push("--reverse");
+        }
+        args.push("-");
+
+        git_with_input(worktree_path, &args, patch).map(|_| ())
+    }
+
+    fn commit(
+        &self,
+        worktree_path: &Path,
+        message: Option<&str>,
+        amend: bool,
+        sign_off: bool,
+    ) -> GitResult<()> {
+        let mut args = vec!["commit"];
+        match message {
+            Some(message) => args.extend(["-m", message]),
+            None => args.push("--no-edit"),
+        }
+        if amend {
+            args.push("--amend");
+        }
+        if sign_off {
+            args.push("--signoff");
+        }
+
+        let output = git_output(worktree_path, &args)?;
+        if !output.status.success() {
+            return Err(GitError::Failed(failure_message(&output)));
+        }
+        Ok(())
+    }
+
+    fn commit_info(&self, repo_path: &Path, rev: &str) -> GitResult<CommitInfo> {
+        let output = git(
+            repo_path,
+            &[
+                "show",
+                "-s",
+                &format!("--format={}", COMMIT_FORMAT),
+                &format!("{}^{{commit}}", rev),
+            ],
+        )?;
+
+        parse_commit(&output)
+    }
+
+    fn log(
+        &self,
+        repo_path: &Path,
+        revisions: &[String],
+        max_count: usize,
+    ) -> GitResult<Vec<CommitInfo>> {
+        let format = format!("--format={}%x1e", COMMIT_FORMAT);
+        let max_count = format!("--max-count={}", max_count);
+        let mut args = vec!["log", "--topo-order", &format, &max_count];
+        args.extend(revisions.iter().map(String::as_str));
+        args.push("--");
+
+        let output = git(repo_path, &args)?;
+        output
+            .split('\x1e')
+            .map(|record| record.trim_start_matches('\n'))
+            .filter(|record| !record.is_empty())
+            .map(parse_commit)
+            .collect()
+    }
+
+    fn commit_refs(&self, repo_path: &Path) -> GitResult<HashMap<String, Vec<String>>> {
+        let output = git(
+            repo_path,
+            &[
+                "for-each-ref",
+                "--format=%(objectname)%00%(*objectname)%00%(symref)%00%(refname:short)",
+                "refs/heads",
+                "refs/remotes",
+                "refs/tags",
+            ],
+        )?;
+
+        let mut refs: HashMap<String, Vec<String>> = HashMap::new();
+        for line in output.lines() {
+            let fields: Vec<&str> = line.split('\0').collect();
+            let [object, peeled, symref, name] = fields.as_slice() else {
+                continue;
+            };
+            // Skip `origin/HEAD` and the like, which only point at another ref
+            if !symref.is_empty() {
+                continue;
+            }
+            let commit = if peeled.is_empty() { object } else { peeled };
+            refs.entry(commit.to_string())
+                .or_default()
+                .push(name.to_string());
+        }
+
+        Ok(refs)
+    }
+
+    fn is_dirty(&self, worktree_path: &Path) -> GitResult<bool> {
+        let status = git(worktree_path, &["status", "--porcelain"])?;
+        Ok(!status.is_empty())
+    }
+
+    fn diff(
+        &self,
+        worktree_path: &Path,
+        target: &DiffTarget,
+        paths: &[String],
+        include_content: bool,
+    ) -> GitResult<Vec<FileDiff>> {
+        // What to compare, and whether the new side is the working tree (which also
+        // brings in untracked files)
+        let (revisions, new_on_disk) = match target {
+            DiffTarget::WorkingTree => (vec![], true),
+            DiffTarget::Staged => (vec!["--cached".to_string()], false),
+            DiffTarget::Parent { parent } => {
+                let parent = parent
+                    .as_deref()
+                    .ok_or(GitError::Failed("No parent branch given".to_string()))?;
+                (
+                    vec![git(worktree_path, &["merge-base", "HEAD", parent])?],
+                    true,
+                )
+            }
+            DiffTarget::Refs { from, to } => (vec![from.clone(), to.clone()], false),
+        };
+
+        let mut args = vec!["--literal-pathspecs", "diff", "--raw", "-p", "-z"];
+        args.extend(DIFF_OPTIONS);
+        args.extend(revisions.iter().map(String::as_str));
+        let output = git_raw(worktree_path, &with_paths(&args, paths))?;
+
+        // NUL-separated ":<old mode> <new mode> <old object> <new object> <status>" entries
+        // with their path (two for renames and copies), an empty field, then the patch.
+        // Unmerged paths get "::..." or "U" entries and no "diff --git" part.
+        let (raw, patch) = output
+            .split_once("\0\0")
+            .unwrap_or((output.trim_end_matches('\0'), ""));
+
+        let mut sections: Vec<String> = Vec::new();
+        let mut in_section = false;
+        for line in patch.split_inclusive('\n') {
+            if line.starts_with("diff --git ") {
+                sections.push(String::new());
+                in_section = true;
+            } else if line.starts_with("diff --cc ") || line.starts_with("* Unmerged path ") {
+                in_section = false;
+            } else if let Some(section) = sections.last_mut().filter(|_| in_section) {
+                section.push_str(line);
+            }
+        }
+        let mut sections = sections.into_iter();
+
+        let mut files: Vec<FileDiff> = Vec::new();
+        let mut fields = raw.split('\0').filter(|field| !field.is_empty());
+        while let Some(info) = fields.next() {
+            let Some(info) = info.strip_prefix(':') else {
+                continue;
+            };
+            let status = info.rsplit(' ').next().unwrap_or_default();
+            let Some(path) = fields.next().map(str::to_string) else {
+                break;
+            };
+
+            if info.starts_with(':') || status == "U" {
+                // Like libgit2, list a conflicted file as modified with nothing to show.
+                // Against the index it is missing, so only the side being compared
+                // with it exists; against the working tree both do.
+                if files.iter().any(|file| {
+                    file.old_path.as_ref() == Some(&path) || file.new_path.as_ref() == Some(&path)
+                }) {
+                    continue;
+                }
+                let mut fields = info.split(' ');
+                let old_mode = fields.next().unwrap_or_default();
+                let new_mode = fields.next().unwrap_or_default();
+                // Combined "::" entries list every stage; they only come from the
+                // working tree, which has no blobs to show
+                let old_blob = (!info.starts_with(':'))
+                    .then(|| fields.next())
+                    .flatten()
+                    .filter(|object| !object.trim_start_matches('0').is_empty())
+                    .map(str::to_string);
+                let has_old = new_on_disk || !old_mode.trim_start_matches('0').is_empty();
+                let has_new = new_on_disk || !new_mode.trim_start_matches('0').is_empty();
+
+                files.push(FileDiff {
+                    old_path: has_old.then(|| path.clone()),
+                    new_path: has_new.then(|| path.clone()),
+                    change: FileChange::Modified,
+                    is_binary: false,
+                    old_content: old_blob
+                        .as_deref()
+                        .filter(|_| include_content)
+                        .and_then(|object| read_blob(worktree_path, object)),
+                    old_blob,
+                    new_blob: None,
+                    new_content: (include_content && new_on_disk)
+                        .then(|| read_worktree_file(worktree_path, &path))
+                        .flatten(),
+                    hunks: vec![],
+                });
+                continue;
+            }
+
+            let code = status.chars().next().unwrap_or('M');
+            let (old_path, new_path) = match code {
+                'R' | 'C' => (Some(path), fields.next().map(str::to_string)),
+                'A' => (None, Some(path)),
+                'D' => (Some(path), None),
+                _ => (Some(path.clone()), Some(path)),
+            };
+
+            let mut objects = info.split(' ').skip(2);
+            let blob_id = |object: Option<&str>| {
+                object
+                    .filter(|object| !object.trim_start_matches('0').is_empty())
+                    .map(str::to_string)
+            };
+            let old_blob = blob_id(objects.next());
+            let new_blob = blob_id(objects.next()).filter(|_| !new_on_disk);
+
+            let section = sections.next().unwrap_or_default();
+            let is_binary = section
+                .lines()
+                .any(|line| line.starts_with("Binary files ") || line == "GIT binary patch");
+
+            let (old_content, new_content) = if include_content && !is_binary {
+                let old_content = old_blob
+                    .as_deref()
+                    .and_then(|object| read_blob(worktree_path, object));
+                let new_content = match (&new_path, &new_blob) {
+                    (Some(path), _) if new_on_disk => read_worktree_file(worktree_path, path),
+                    (Some(_), Some(object)) => read_blob(worktree_path, object),
+                    _ => None,
+                };
+                (old_content, new_content)
+            } else {
+                (None, None)
+            };
+
+            files.push(FileDiff {
+                old_path,
+                new_path,
+                change: parse_change(code).unwrap_or(FileChange::Modified),
+                is_binary,
+                old_blob,
+                new_blob,
+                old_content,
+                new_content,
+                hunks: if is_binary {
+                    vec![]
+                } else {
+                    parse_hunks(&section)
+                },
+            });
+        }
+
+        if new_on_disk {
+            let untracked = git_raw(
+                worktree_path,
+                &with_paths(
+                    &[
+                        "--literal-pathspecs",
+                        "ls-files",
+                        "--others",
+                        "--exclude-standard",
+                        "-z",
+                    ],
+                    paths,
+                ),
+            )?;
+            for path in untracked.split('\0').filter(|path| !path.is_empty()) {
+                files.push(untracked_diff(worktree_path, path, include_content));
+            }
+            // Keep the path order libgit2 uses
+            files.sort_by(|a, b| {
+                let path = |file: &FileDiff| file.new_path.clone().or(file.old_path.clone());
+                path(a).cmp(&path(b))
+            });
+        }
+
+        Ok(files)
+    }
+
+    fn diff_stat(&self, repo_path: &Path, from: &str, to: &str) -> GitResult<Vec<FileStat>> {
+        let output = git_raw(
+            repo_path,
+            &["diff", "--numstat", "-z", "-M", "--no-ext-diff", from, to],
+        )?;
+
+        // "<insertions>\t<deletions>\t<path>\0", or for a rename an empty path followed
+        // by "<old path>\0<new path>\0". Binary files count "-".
+        let mut stats = Vec::new();
+        let mut fields = output.split('\0');
+        while let Some(field) = fields.next() {
+            let mut counts = field.splitn(3, '\t');
+            let (Some(insertions), Some(deletions), Some(path)) =
+                (counts.next(), counts.next(), counts.next())
+            else {
+                continue;
+            };
+            let (old_path, path) = if path.is_empty() {
+                let old_path = fields.next().map(str::to_string);
+                (old_path, fields.next().unwrap_or_default().to_string())
+            } else {
+                (None, path.to_string())
+            };
+
+            stats.push(FileStat {
+                path,
+                old_path,
+                insertions: insertions.parse().unwrap_or(0),
+                deletions: deletions.parse().unwrap_or(0),
+                is_binary: insertions == "-",
+            });
+        }
+
+        Ok(stats)
+    }
+
+    fn blob_at(&self, repo_path: &Path, rev: &str, path: &str) -> GitResult<Option<Vec<u8>>> {
+        // Entries look like "<mode> <type> <object>\t<path>"
+        let entries = git_raw(
+            repo_path,
+            &["ls-tree", "-z", "--full-tree", rev, "--", path],
+        )?;
+        let Some(object) = entries.split('\0').find_map(|entry| {
+            let (info, _) = entry.split_once('\t')?;
+            let mut fields = info.split(' ');
+            (fields.nth(1)? == "blob").then(|| fields.next().map(str::to_string))?
+        }) else {
+            return Ok(None);
+        };
+
+        let output = git_output(repo_path, &["cat-file", "blob", &object])?;
+        if !output.status.success() {
+            return Err(GitError::Failed(failure_message(&output)));
+        }
+        Ok(Some(output.stdout))
+    }
+
+    fn upstream(&self, repo_path: &Path, branch_name: &str) -> GitResult<Option<String>> {
+        let upstream = git(
+            repo_path,
+            &[
+                "for-each-ref",
+                "--format=%(upstream:short)",
+                &format!("refs/heads/{}", branch_name),
+            ],
+        )?;
+
+        Ok(Some(upstream).filter(|upstream| !upstream.is_empty()))
+    }
+
+    fn list_branches(&self, repo_path: &Path) -> GitResult<Vec<Branch>> {
+        let output = git(
+            repo_path,
+            &[
+                "for-each-ref",
+                "--format=%(refname)%00%(symref)%00%(refname:short)%00%(upstream:short)%00\
+                 %(objectname)%00%(parent)%00%(contents:subject)%00%(contents:body)%00\
+                 %(authorname)%00%(authoremail:trim)%00%(authordate:unix)%00\
+                 %(committerdate:unix)%1e",
+                "refs/heads",
+                "refs/remotes",
+            ],
+        )?;
+
+        let mut branches = Vec::new();
+        for record in output.split('\x1e') {
+            let record = record.trim_start_matches('\n');
+            let mut fields = record.splitn(5, '\0');
+            let (Some(refname), Some(symref), Some(name), Some(upstream), Some(commit)) = (
+                fields.next(),
+                fields.next(),
+                fields.next(),
+                fields.next(),
+                fields.next(),
+            ) else {
+                continue;
+            };
+            // Skip `origin/HEAD` and the like, which only point at another ref
+            if !symref.is_empty() {
+                continue;
+            }
+
+            branches.push(Branch {
+                name: name.to_string(),
+                is_remote: refname.starts_with("refs/remotes/"),
+                upstream: Some(upstream.to_string()).filter(|upstream| !upstream.is_empty()),
+                worktree_path: None,
+                last_commit: parse_commit(commit)?,
+            });
+        }
+
+        Ok(branches)
+    }
+
+    fn divergence(
+        &self,
+        repo_path: &Path,
+        pairs: &[(String, String)],
+    ) -> GitResult<Vec<Divergence>> {
+        pairs
+            .iter()
+            .map(|(left, right)| {
+                let (ahead, behind) = self.ahead_behind(repo_path, left, right)?;
+                // `merge-base` exits with 1 when the histories are unrelated
+                let output = git_output(repo_path, &["merge-base", left, right])?;
+                let merge_base = output
+                    .status
+                    .success()
+                    .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string());
+
+                Ok(Divergence {
+                    ahead,
+                    behind,
+                    merge_base,
+                })
+            })
+            .collect()
+    }
+
+    fn fork_point(&self, repo_path: &Path, upstream: &str, branch: &str) -> GitResult<String> {
+        let output = git_output(repo_path, &["merge-base", "--fork-point", upstream, branch])?;
+        if output.status.success() {
+            return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+        }
+        git(repo_path, &["merge-base", upstream, branch])
+    }
+
+    fn ahead_behind(&self, repo_path: &Path, left: &str, right: &str) -> GitResult<(usize, usize)> {
+        let output = git(
+            repo_path,
+            &[
+                "rev-list",
+                "--left-right",
+                "--count",
+                &format!("{}...{}", left, right),
+            ],
+        )?;
+
+        let parts: Vec<&str> = output.split('\t').collect();
+        match parts.as_slice() {
+            [ahead, behind] => Ok((ahead.parse().unwrap_or(0), behind.parse().unwrap_or(0))),
+            _ => Err(GitError::Failed(format!(
+                "Unexpected rev-list output: {}",
+                output
+            ))),
+        }
+    }
+
+    fn create_branch(&self, repo_path: &Path, branch_name: &str, target: &str) -> GitResult<()> {
+        git(repo_path, &["branch", "--", branch_name, target]).map(|_| ())
+    }
+
+    fn rename_branch(&self, repo_path: &Path, old_name: &str, new_name: &str) -> GitResult<()> {
+        git(repo_path, &["branch", "-m", "--", old_name, new_name]).map(|_| ())
+    }
+
+    fn delete_branch(&self, repo_path: &Path, branch_name: &str) -> GitResult<()> {
+        git(repo_path, &["branch", "-D", "--", branch_name]).map(|_| ())
+    }
+
+    fn update_ref(&self, repo_path: &Path, refname: &str, target: &str) -> GitResult<()> {
+        git(repo_path, &["update-ref", refname, target]).map(|_| ())
+    }
+
+    fn delete_ref(&self, repo_path: &Path, refname: &str) -> GitResult<()> {
+        git(repo_path, &["update-ref", "-d", refname]).map(|_| ())
+    }
+
+    fn list_refs(&self, repo_path: &Path, prefix: &str) -> GitResult<Vec<(String, CommitInfo)>> {
+        let output = git(
+            repo_path,
+            &[
+                "for-each-ref",
+                "--format=%(refname)%00%(objectname)%00%(parent)%00%(contents:subject)%00\
+                 %(contents:body)%00%(authorname)%00%(authoremail:trim)%00\
+                 %(authordate:unix)%00%(committerdate:unix)%1e",
+                prefix,
+            ],
+        )?;
+
+        let mut refs = Vec::new();
+        for record in output.split('\x1e') {
+            let record = record.trim_start_matches('\n');
+            let Some((refname, commit)) = record.split_once('\0') else {
+                continue;
+            };
+            refs.push((refname.to_string(), parse_commit(commit)?));
+        }
+
+        Ok(refs)
+    }
+
+    fn ref_targets(&self, repo_path: &Path, prefix: &str) -> GitResult<Vec<(String, String)>> {
+        let output = git(
+            repo_path,
+            &[
+                "for-each-ref",
+                "--format=%(refname)%00%(objectname)",
+                prefix,
+            ],
+        )?;
+
+        Ok(output
+            .lines()
+            .filter_map(|line| line.split_once('\0'))
+            .map(|(refname, object)| (refname.to_string(), object.to_string()))
+            .collect())
+    }
+
+    fn fetch(
+        &self,
+        repo_path: &Path,
+        remote: Option<&str>,
+        prune: bool,
+        transfer: &Transfer,
+    ) -> GitResult<()> {
+        let mut args = vec!["fetch", "--progress"];
+        if prune {
+            args.push("--prune");
+        }
+        match remote {
+            Some(remote) => args.extend(["--", remote]),
+            None => args.push("--all"),
+        }
+
+        let output = git_transfer(repo_path, &args, transfer)?;
+        if !output.status.success() {
+            return Err(GitError::Failed(failure_message(&output)));
+        }
+        Ok(())
+    }
+
+    fn pull(
+        &self,
+        worktree_path: &Path,
+        rebase: bool,
+        transfer: &Transfer,
+    ) -> GitResult<MergeResult> {
+        let mode = if rebase { "--rebase" } else { "--no-rebase" };
+        let output = git_transfer(
+            worktree_path,
+            &["pull", "--progress", "--no-edit", mode],
+            transfer,
+        )?;
+
+        if !output.status.success() {
+            let mut result = conflict_result(worktree_path, &output)?;
+            // The fetch fills stderr, so the conflict report on stdout says more
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            if !result.conflicts.is_empty() && !stdout.trim().is_empty() {
+                result.message = stdout.trim().to_string();
+            }
+            return Ok(result);
+        }
+
+        Ok(MergeResult {
+            success: true,
+            message: "Pull successful".to_string(),
+            conflicts: vec![],
+            worktree_path: Some(worktree_path.to_string_lossy().to_string()),
+            commit: Some(git(worktree_path, &["rev-parse", "HEAD"])?),
+        })
+    }
+
+    fn push(
+        &self,
+        worktree_path: &Path,
+        remote: &str,
+        set_upstream: bool,
+        force_with_lease: bool,
+        transfer: &Transfer,
+    ) -> GitResult<()> {
+        let mut args = vec!["push", "--progress"];
+        if set_upstream {
+            args.push("--set-upstream");
+        }
+        if force_with_lease {
+            args.push("--force-with-lease");
+        }
+        args.extend(["--", remote, "HEAD"]);
+
+        let output = git_transfer(worktree_path, &args, transfer)?;
+        if !output.status.success() {
+            return Err(GitError::Failed(failure_message(&output)));
+        }
+        Ok(())
+    }
+
+    fn list_stashes(&self, repo_path: &Path) -> GitResult<Vec<CommitInfo>> {
+        let format = format!("--format={}%x1e", COMMIT_FORMAT);
+        let output = git(repo_path, &["stash", "list", &format])?;
+        output
+            .split('\x1e')
+            .map(|record| record.trim_start_matches('\n'))
+            .filter(|record| !record.is_empty())
+            .map(parse_commit)
+            .collect()
+    }
+
+    fn stash_push(
+        &self,
+        worktree_path: &Path,
+        message: Option<&str>,
+        include_untracked: bool,
+        paths: &[String],
+    ) -> GitResult<bool> {
+        // `stash push` succeeds without creating anything when there is nothing to stash
+        let stash_tip = || -> GitResult<Option<String>> {
+            let output = git_output(
+                worktree_path,
+                &["rev-parse", "-q", "--verify", "refs/stash"],
+            )?;
+            Ok(output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string()))
+        };
+        let before = stash_tip()?;
+
+        let mut args = vec!["stash", "push"];
+        if let Some(message) = message {
+            args.extend(["-m", message]);
+        }
+        if include_untracked {
+            args.push("--include-untracked");
+        }
+        git(worktree_path, &with_paths(&args, paths))?;
+
+        Ok(stash_tip()? != before)
+    }
+
+    fn stash_apply(&self, worktree_path: &Path, stash: &str) -> GitResult<MergeResult> {
+        let apply_output = git_output(worktree_path, &["stash", "apply", stash])?;
+        if !apply_output.status.success() {
+            return conflict_result(worktree_path, &apply_output);
+        }
+
+        Ok(MergeResult {
+            success: true,
+            message: "Stash applied".to_string(),
+            conflicts: vec![],
+            worktree_path: Some(worktree_path.to_string_lossy().to_string()),
+            commit: None,
+        })
+    }
+
+    fn stash_drop(&self, repo_path: &Path, stash: &str) -> GitResult<()> {
+        git(repo_path, &["stash", "drop", stash]).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    /// Fresh directory under the system temp dir, emptied if a previous run left it
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("codecraft-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn clone(root: &Path, remote: &Path, name: &str) -> PathBuf {
+        git(root, &["clone", "-q", &remote.to_string_lossy(), name]).unwrap();
+        let path = root.join(name);
+        git(&path, &["config", "user.name", "Test"]).unwrap();
+        git(&path, &["config", "user.email", "test@example.com"]).unwrap();
+        path
+    }
+
+    fn commit(worktree: &Path, file: &str, content: &str) -> String {
+        fs::write(worktree.join(file), content).unwrap();
+        git(worktree, &["add", file]).unwrap();
+        git(worktree, &["commit", "-q", "-m", content]).unwrap();
+        git(worktree, &["rev-parse", "HEAD"]).unwrap()
+    }
+
+    /// Run `op` with a transfer that records every progress line
+    fn recorded<T>(op: impl FnOnce(&Transfer) -> T) -> (T, Vec<String>) {
+        let lines = Mutex::new(Vec::new());
+        let on_progress = |line: &str| lines.lock().push(line.to_string());
+        let transfer = Transfer {
+            on_progress: &on_progress,
+            env: &[],
+        };
+        let result = op(&transfer);
+        (result, lines.into_inner())
+    }
+
+    #[test]
+    fn fetch_pull_and_push_against_a_bare_remote() {
+        let root = scratch("transfer");
+        let remote = root.join("remote.git");
+        git(&root, &["init", "-q", "--bare", "-b", "main", "remote.git"]).unwrap();
+
+        let ours = clone(&root, &remote, "ours");
+        commit(&ours, "file.txt", "base\n");
+        git(&ours, &["push", "-q", "origin", "main"]).unwrap();
+        let theirs = clone(&root, &remote, "theirs");
+
+        // A new branch has no upstream until it is pushed with --set-upstream
+        git(&ours, &["checkout", "-q", "-b", "topic"]).unwrap();
+        let tip = commit(&ours, "topic.txt", "topic\n");
+        let (pushed, lines) =
+            recorded(|transfer| CliBackend.push(&ours, "origin", true, false, transfer));
+        pushed.unwrap();
+        assert!(!lines.is_empty());
+        assert_eq!(git(&remote, &["rev-parse", "topic"]).unwrap(), tip);
+        assert_eq!(
+            git(&ours, &["rev-parse", "--abbrev-ref", "topic@{upstream}"]).unwrap(),
+            "origin/topic"
+        );
+
+        let (fetched, lines) =
+            recorded(|transfer| CliBackend.fetch(&theirs, Some("origin"), false, transfer));
+        fetched.unwrap();
+        assert!(lines.iter().any(|line| line.contains("origin/topic")));
+        assert_eq!(git(&theirs, &["rev-parse", "origin/topic"]).unwrap(), tip);
+
+        // Both sides change the same line, so pulling leaves a conflict to resolve
+        let upstream = commit(&theirs, "file.txt", "theirs\n");
+        git(&theirs, &["push", "-q", "origin", "main"]).unwrap();
+        git(&ours, &["checkout", "-q", "main"]).unwrap();
+        commit(&ours, "file.txt", "ours\n");
+
+        let (pulled, lines) = recorded(|transfer| CliBackend.pull(&ours, false, transfer));
+        let pulled = pulled.unwrap();
+        assert!(!pulled.success);
+        assert_eq!(pulled.conflicts, vec!["file.txt".to_string()]);
+        assert!(pulled.message.contains("CONFLICT"), "{}", pulled.message);
+        assert_eq!(pulled.commit, None);
+        assert!(!lines.is_empty());
+        assert_eq!(git(&ours, &["rev-parse", "origin/main"]).unwrap(), upstream);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn fetch_reads_the_remote_as_a_name_only() {
+        let root = scratch("fetch-option");
+        git(&root, &["init", "-q", "repo"]).unwrap();
+        let marker = root.join("ran");
+        let remote = format!("--upload-pack=touch {}", marker.display());
+
+        let (fetched, _) = recorded(|transfer| {
+            CliBackend.fetch(&root.join("repo"), Some(&remote), false, transfer)
+        });
+        assert!(fetched.is_err());
+        assert!(!marker.exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn parse_status_v2_reads_every_record_kind() {
+        // `git status --porcelain=v2 -z --untracked-files=all --ignored --branch`
+        let output = concat!(
+            "# branch.oid 1d8e14e9522c2a3ad7faa6ba5c59f372a30457af\0",
+            "# branch.head main\0",
+            "1 MM N... 100644 100644 100644 28ce6a8b26aa170e1de65536fe8abe1832bd3242 ",
+            "191dfc3290bb610fe68848fd125de86d422a5282 mod.txt\0",
+            "2 R. N... 100644 100644 100644 d68dd4031d2ad5b7a3829ad7df6635e27a7daa22 ",
+            "d68dd4031d2ad5b7a3829ad7df6635e27a7daa22 R100 new name.txt\0old.txt\0",
+            "u UU N... 100644 100644 100644 100644 f2ad6c76f0115a6ba5b00456a849810e7ec0af20 ",
+            "b19a1e93bec1317dc6097229e12afaffbfa74dc2 950b81b7eee953d050aa05a641f8e056c85dd1bd ",
+            "conflict.txt\0",
+            "1 .M SC.U 160000 160000 160000 4b825dc642cb6eb9a060e54bf8d69288fbee4904 ",
+            "4b825dc642cb6eb9a060e54bf8d69288fbee4904 vendor/lib\0",
+            "? untracked file.txt\0",
+            "! ignored.log\0",
+        );
+        let entries = parse_status_v2(output);
+        let paths: Vec<&str> = entries.iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "mod.txt",
+                "new name.txt",
+                "conflict.txt",
+                "vendor/lib",
+                "untracked file.txt",
+                "ignored.log"
+            ]
+        );
+
+        let modified = &entries[0];
+        assert_eq!(modified.staged, Some(FileChange::Modified));
+        assert_eq!(modified.unstaged, Some(FileChange::Modified));
+        assert_eq!(modified.submodule, None);
+
+        // The original path is its own record, not the start of the next entry
+        let renamed = &entries[1];
+        assert_eq!(renamed.staged, Some(FileChange::Renamed));
+        assert_eq!(renamed.unstaged, None);
+        assert_eq!(renamed.original_path.as_deref(), Some("old.txt"));
+
+        let conflicted = &entries[2];
+        assert_eq!(conflicted.conflict, Some(ConflictKind::BothModified));
+        assert_eq!((conflicted.staged, conflicted.unstaged), (None, None));
+
+        let submodule = &entries[3];
+        assert_eq!(submodule.unstaged, Some(FileChange::Modified));
+        assert_eq!(
+            submodule.submodule,
+            Some(SubmoduleState {
+                commit_changed: true,
+                has_tracked_changes: false,
+                has_untracked_changes: true,
+            })
+        );
+
+        assert!(entries[4].untracked && !entries[4].ignored);
+        assert!(entries[5].ignored && !entries[5].untracked);
+    }
+
+    #[test]
+    fn parse_status_v2_reads_every_conflict_kind() {
+        let record = |xy: &str, path: &str| {
+            format!(
+                "u {} N... 100644 100644 100644 100644 {} {} {} {}\0",
+                xy,
+                "0".repeat(40),
+                "0".repeat(40),
+                "0".repeat(40),
+                path
+            )
+        };
+        let output: String = ["UU", "AA", "DD", "AU", "UA", "DU", "UD"]
+            .iter()
+            .map(|xy| record(xy, &format!("{} file", xy)))
+            .collect();
+
+        let conflicts: Vec<_> = parse_status_v2(&output)
+            .into_iter()
+            .map(|entry| (entry.path, entry.conflict))
+            .collect();
+        assert_eq!(
+            conflicts,
+            [
+                ("UU file".to_string(), Some(ConflictKind::BothModified)),
+                ("AA file".to_string(), Some(ConflictKind::BothAdded)),
+                ("DD file".to_string(), Some(ConflictKind::BothDeleted)),
+                ("AU file".to_string(), Some(ConflictKind::AddedByUs)),
+                ("UA file".to_string(), Some(ConflictKind::AddedByThem)),
+                ("DU file".to_string(), Some(ConflictKind::DeletedByUs)),
+                ("UD file".to_string(), Some(ConflictKind::DeletedByThem)),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_worktree_list_reads_every_stanza() {
+        // `git worktree list --porcelain`
+        let output = concat!(
+            "worktree /repo\n",
+            "HEAD 1d8e14e9522c2a3ad7faa6ba5c59f372a30457af\n",
+            "branch refs/heads/main\n",
+            "\n",
+            "worktree /det\n",
+            "HEAD afac3aaa8f09074163af4d49c2ef8973d9d31b6b\n",
+            "detached\n",
+            "\n",
+            "worktree /gone\n",
+            "HEAD 1d8e14e9522c2a3ad7faa6ba5c59f372a30457af\n",
+            "branch refs/heads/gone\n",
+            "prunable gitdir file points to non-existent location\n",
+            "\n",
+            "worktree /locked\n",
+            "HEAD 1d8e14e9522c2a3ad7faa6ba5c59f372a30457af\n",
+            "branch refs/heads/feature/lk\n",
+            "locked on a usb drive\n",
+            "\n",
+            "worktree /plain-lock\n",
+            "HEAD 1d8e14e9522c2a3ad7faa6ba5c59f372a30457af\n",
+            "branch refs/heads/pl\n",
+            "locked\n",
+        );
+        let worktrees = parse_worktree_list(output);
+        assert_eq!(worktrees.len(), 5);
+
+        let main = &worktrees[0];
+        assert_eq!(main.path, "/repo");
+        assert_eq!(main.head, "1d8e14e9522c2a3ad7faa6ba5c59f372a30457af");
+        assert_eq!(main.branch.as_deref(), Some("main"));
+        assert!(main.is_main && !main.is_detached && !main.is_locked && !main.is_prunable);
+
+        let detached = &worktrees[1];
+        assert!(detached.is_detached && !detached.is_main);
+        assert_eq!(detached.branch, None);
+
+        let gone = &worktrees[2];
+        assert!(gone.is_prunable && !gone.is_locked);
+        assert_eq!(
+            gone.prunable_reason.as_deref(),
+            Some("gitdir file points to non-existent location")
+        );
+
+        let locked = &worktrees[3];
+        assert_eq!(locked.branch.as_deref(), Some("feature/lk"));
+        assert!(locked.is_locked && !locked.is_prunable);
+        assert_eq!(locked.lock_reason.as_deref(), Some("on a usb drive"));
+
+        // The last stanza has no blank line after it
+        let plain_lock = &worktrees[4];
+        assert_eq!(plain_lock.path, "/plain-lock");
+        assert!(plain_lock.is_locked);
+        assert_eq!(plain_lock.lock_reason, None);
+    }
+
+    #[test]
+    fn parse_worktree_list_reads_a_bare_repository() {
+        let worktrees = parse_worktree_list("worktree /repo.git\nbare\n\n");
+        assert_eq!(worktrees.len(), 1);
+        assert!(worktrees[0].is_bare && worktrees[0].is_main);
+        assert_eq!(worktrees[0].head, "");
+        assert_eq!(worktrees[0].branch, None);
+    }
+
+    #[test]
+    fn parse_merge_tree_reads_a_clean_merge() {
+        // `git merge-tree --write-tree -z ours side` with no overlapping changes
+        let merged = parse_merge_tree("29ea3fc6bdb2990617f7628aab8003588cd116c4\0");
+
+        assert_eq!(merged.tree, "29ea3fc6bdb2990617f7628aab8003588cd116c4");
+        assert!(merged.conflicts.is_empty());
+        assert!(merged.messages.is_empty());
+    }
+
+    #[test]
+    fn parse_merge_tree_reads_content_and_rename_delete_conflicts() {
+        // `git merge-tree --write-tree -z ours theirs`: both sides edited f.txt,
+        // ours renamed g.txt to h.txt and theirs deleted it
+        let output = concat!(
+            "0cd5986302d416924d4c93d5adb485441fbe266b\0",
+            "100644 de980441c3ab03a8c07dda1ad27b8a11f39deb1e 1\tf.txt\0",
+            "100644 070e23ddba2c535bad0c84447179d4dd26c4668a 2\tf.txt\0",
+            "100644 4e367944018b4018b1832ea3d909d2be090c88a2 3\tf.txt\0",
+            "100644 2fa992c0b8b5c6acd2bdd4fa31de29d29799bdd5 1\th.txt\0",
+            "100644 2fa992c0b8b5c6acd2bdd4fa31de29d29799bdd5 2\th.txt\0",
+            "\0",
+            "1\0f.txt\0Auto-merging\0Auto-merging f.txt\n\0",
+            "1\0f.txt\0CONFLICT (contents)\0CONFLICT (content): Merge conflict in f.txt\n\0",
+            "2\0h.txt\0g.txt\0CONFLICT (rename/delete)\0",
+            "CONFLICT (rename/delete): g.txt renamed to h.txt in ours, but deleted in theirs.\n\0",
+        );
+
+        let merged = parse_merge_tree(output);
+        assert_eq!(merged.tree, "0cd5986302d416924d4c93d5adb485441fbe266b");
+        assert_eq!(
+            merged.conflicts,
+            [
+                ("f.txt".to_string(), ConflictKind::BothModified),
+                ("h.txt".to_string(), ConflictKind::DeletedByThem),
+            ]
+        );
+        assert_eq!(
+            merged.messages,
+            [
+                "Auto-merging f.txt",
+                "CONFLICT (content): Merge conflict in f.txt",
+                "CONFLICT (rename/delete): g.txt renamed to h.txt in ours, but deleted in theirs.",
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_tree_matches_the_parsed_output_of_real_merges() {
+        let root = scratch("merge-tree");
+        let repo = root.join("repo");
+        git(&root, &["init", "-q", "-b", "main", "repo"]).unwrap();
+        git(&repo, &["config", "user.name", "Test"]).unwrap();
+        git(&repo, &["config", "user.email", "test@example.com"]).unwrap();
+        commit(&repo, "g.txt", "keep\n");
+        commit(&repo, "f.txt", "a\nb\nc\n");
+
+        git(&repo, &["checkout", "-q", "-b", "ours"]).unwrap();
+        git(&repo, &["mv", "g.txt", "h.txt"]).unwrap();
+        commit(&repo, "f.txt", "a\nours\nc\n");
+        git(&repo, &["checkout", "-q", "-b", "theirs", "main"]).unwrap();
+        git(&repo, &["rm", "-q", "g.txt"]).unwrap();
+        commit(&repo, "f.txt", "a\ntheirs\nc\n");
+        git(&repo, &["checkout", "-q", "-b", "side", "main"]).unwrap();
+        commit(&repo, "new.txt", "x\n");
+
+        let clean = CliBackend.merge_tree(&repo, "ours", "side").unwrap();
+        assert!(clean.conflicts.is_empty() && clean.messages.is_empty());
+        let expected = git(&repo, &["merge-tree", "--write-tree", "ours", "side"]).unwrap();
+        assert_eq!(clean.tree, expected);
+
+        let conflicted = CliBackend.merge_tree(&repo, "ours", "theirs").unwrap();
+        assert_eq!(
+            conflicted.conflicts,
+            [
+                ("f.txt".to_string(), ConflictKind::BothModified),
+                ("h.txt".to_string(), ConflictKind::DeletedByThem),
+            ]
+        );
+        assert!(conflicted
+            .messages
+            .iter()
+            .any(|message| message.starts_with("CONFLICT (rename/delete)")));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
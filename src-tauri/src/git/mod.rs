@@ -0,0 +1,525 @@
+mod cli;
+mod native;
+
+use crate::commands::branch::Branch;
+use crate::commands::commit::CommitInfo;
+use crate::commands::diff::{DiffTarget, FileDiff, FileStat};
+use crate::commands::divergence::Divergence;
+use crate::commands::status::{ConflictKind, FileStatus};
+use crate::commands::worktree::{MergeResult, MergeStrategy, Worktree};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+pub use cli::CliBackend;
+pub use native::NativeBackend;
+
+/// Error returned by a git backend
+#[derive(Debug)]
+pub enum GitError {
+    /// The backend does not implement this operation; the caller should fall back to the CLI
+    Unsupported(&'static str),
+    /// The operation ran and failed
+    Failed(String),
+}
+
+pub type GitResult<T> = Result<T, GitError>;
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitError::Unsupported(op) => {
+                write!(f, "Operation not supported by this backend: {}", op)
+            }
+            GitError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<GitError> for String {
+    fn from(error: GitError) -> Self {
+        error.to_string()
+    }
+}
+
+/// How an operation that talks to a remote reports progress and asks for credentials
+pub struct Transfer<'a> {
+    /// Receives each progress line as git reports it, e.g. `Receiving objects:  45% (9/20)`
+    pub on_progress: &'a (dyn Fn(&str) + Sync),
+    /// Extra environment for git, such as the askpass helper that answers prompts
+    pub env: &'a [(String, String)],
+}
+
+/// Outcome of merging two commits into a tree, without any worktree or index
+pub struct MergeTree {
+    /// Tree of the merge result; conflicted files hold their conflict markers
+    pub tree: String,
+    pub conflicts: Vec<(String, ConflictKind)>,
+    /// What git reported about the merge, e.g. `CONFLICT (content): Merge conflict in f`
+    pub messages: Vec<String>,
+}
+
+/// Operations the worktree commands need from git.
+///
+/// Every method defaults to `GitError::Unsupported`, so a backend only implements
+/// what it can do natively and `with_backend` routes the rest to the CLI.
+pub trait GitBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// List all worktrees of the repository containing `repo_path`, main worktree first
+    fn list_worktrees(&self, _repo_path: &Path) -> GitResult<Vec<Worktree>> {
+        Err(GitError::Unsupported("list_worktrees"))
+    }
+
+    /// Absolute path of the git directory shared by all worktrees
+    fn common_dir(&self, _path: &Path) -> GitResult<PathBuf> {
+        Err(GitError::Unsupported("common_dir"))
+    }
+
+    /// Absolute path of the git directory private to the worktree containing `path`
+    fn git_dir(&self, _path: &Path) -> GitResult<PathBuf> {
+        Err(GitError::Unsupported("git_dir"))
+    }
+
+    /// Resolve a revision to the full SHA of the commit it points at
+    fn resolve_commit(&self, _repo_path: &Path, _rev: &str) -> GitResult<String> {
+        Err(GitError::Unsupported("resolve_commit"))
+    }
+
+    /// Add a worktree at `worktree_path` on a new branch starting at `start_point`
+    fn add_worktree(
+        &self,
+        _repo_path: &Path,
+        _worktree_path: &Path,
+        _branch_name: &str,
+        _start_point: &str,
+    ) -> GitResult<()> {
+        Err(GitError::Unsupported("add_worktree"))
+    }
+
+    fn remove_worktree(
+        &self,
+        _repo_path: &Path,
+        _worktree_path: &Path,
+        _force: bool,
+    ) -> GitResult<()> {
+        Err(GitError::Unsupported("remove_worktree"))
+    }
+
+    /// Add a worktree at `worktree_path` with the existing `branch_name` checked out
+    fn add_worktree_for_branch(
+        &self,
+        _repo_path: &Path,
+        _worktree_path: &Path,
+        _branch_name: &str,
+    ) -> GitResult<()> {
+        Err(GitError::Unsupported("add_worktree_for_branch"))
+    }
+
+    /// Add a worktree on a new branch that tracks the remote-tracking branch `upstream`
+    fn add_worktree_tracking(
+        &self,
+        _repo_path: &Path,
+        _worktree_path: &Path,
+        _branch_name: &str,
+        _upstream: &str,
+    ) -> GitResult<()> {
+        Err(GitError::Unsupported("add_worktree_tracking"))
+    }
+
+    /// Add an empty worktree on a new branch with no history
+    fn add_worktree_orphan(
+        &self,
+        _repo_path: &Path,
+        _worktree_path: &Path,
+        _branch_name: &str,
+    ) -> GitResult<()> {
+        Err(GitError::Unsupported("add_worktree_orphan"))
+    }
+
+    /// Add a worktree with a detached HEAD at `commit`
+    fn add_worktree_detached(
+        &self,
+        _repo_path: &Path,
+        _worktree_path: &Path,
+        _commit: &str,
+    ) -> GitResult<()> {
+        Err(GitError::Unsupported("add_worktree_detached"))
+    }
+
+    /// Keep `prune` and removal away from a worktree, recording why
+    fn lock_worktree(
+        &self,
+        _repo_path: &Path,
+        _worktree_path: &Path,
+        _reason: Option<&str>,
+    ) -> GitResult<()> {
+        Err(GitError::Unsupported("lock_worktree"))
+    }
+
+    fn unlock_worktree(&self, _repo_path: &Path, _worktree_path: &Path) -> GitResult<()> {
+        Err(GitError::Unsupported("unlock_worktree"))
+    }
+
+    /// Move a linked worktree to `new_path`, which must not exist yet
+    fn move_worktree(
+        &self,
+        _repo_path: &Path,
+        _worktree_path: &Path,
+        _new_path: &Path,
+    ) -> GitResult<()> {
+        Err(GitError::Unsupported("move_worktree"))
+    }
+
+    /// Delete the administrative files of every prunable worktree
+    fn prune_worktrees(&self, _repo_path: &Path) -> GitResult<()> {
+        Err(GitError::Unsupported("prune_worktrees"))
+    }
+
+    /// Reconnect worktrees with the repository after either was moved by hand. Moved
+    /// linked worktrees can only be found when their new `paths` are given.
+    fn repair_worktrees(&self, _repo_path: &Path, _paths: &[String]) -> GitResult<()> {
+        Err(GitError::Unsupported("repair_worktrees"))
+    }
+
+    /// Merge `source_branch` into whatever is checked out in `worktree_path`.
+    /// `MergeStrategy::Rebase` only fast-forwards; the caller rebases the source first.
+    fn merge(
+        &self,
+        _worktree_path: &Path,
+        _source_branch: &str,
+        _strategy: MergeStrategy,
+    ) -> GitResult<MergeResult> {
+        Err(GitError::Unsupported("merge"))
+    }
+
+    /// Abandon an in-progress merge and restore the pre-merge state
+    fn abort_merge(&self, _worktree_path: &Path) -> GitResult<()> {
+        Err(GitError::Unsupported("abort_merge"))
+    }
+
+    /// Merge `theirs` into `ours` in the object database only. Nothing is checked out
+    /// and no index or ref changes.
+    fn merge_tree(&self, _repo_path: &Path, _ours: &str, _theirs: &str) -> GitResult<MergeTree> {
+        Err(GitError::Unsupported("merge_tree"))
+    }
+
+    /// Rebase whatever is checked out in `worktree_path` onto `upstream`
+    fn rebase(&self, _worktree_path: &Path, _upstream: &str) -> GitResult<MergeResult> {
+        Err(GitError::Unsupported("rebase"))
+    }
+
+    /// Replay the commits of the worktree's branch that follow `upstream` onto `onto`
+    fn rebase_onto(
+        &self,
+        _worktree_path: &Path,
+        _onto: &str,
+        _upstream: &str,
+    ) -> GitResult<MergeResult> {
+        Err(GitError::Unsupported("rebase_onto"))
+    }
+
+    /// Resume a stopped rebase after its conflicts have been staged
+    fn continue_rebase(&self, _worktree_path: &Path) -> GitResult<MergeResult> {
+        Err(GitError::Unsupported("continue_rebase"))
+    }
+
+    /// Skip the commit a rebase stopped on and resume
+    fn skip_rebase(&self, _worktree_path: &Path) -> GitResult<MergeResult> {
+        Err(GitError::Unsupported("skip_rebase"))
+    }
+
+    /// Abandon an in-progress rebase and restore the original branch
+    fn abort_rebase(&self, _worktree_path: &Path) -> GitResult<()> {
+        Err(GitError::Unsupported("abort_rebase"))
+    }
+
+    /// Apply `commits`, in order, on top of whatever is checked out in `worktree_path`.
+    /// `record_origin` adds a `(cherry picked from commit ...)` trailer to each message.
+    fn cherry_pick(
+        &self,
+        _worktree_path: &Path,
+        _commits: &[String],
+        _record_origin: bool,
+    ) -> GitResult<MergeResult> {
+        Err(GitError::Unsupported("cherry_pick"))
+    }
+
+    /// Resume a stopped cherry-pick after its conflicts have been staged
+    fn continue_cherry_pick(&self, _worktree_path: &Path) -> GitResult<MergeResult> {
+        Err(GitError::Unsupported("continue_cherry_pick"))
+    }
+
+    /// Skip the commit a cherry-pick stopped on and resume
+    fn skip_cherry_pick(&self, _worktree_path: &Path) -> GitResult<MergeResult> {
+        Err(GitError::Unsupported("skip_cherry_pick"))
+    }
+
+    /// Abandon an in-progress cherry-pick and restore the branch to where it was
+    fn abort_cherry_pick(&self, _worktree_path: &Path) -> GitResult<()> {
+        Err(GitError::Unsupported("abort_cherry_pick"))
+    }
+
+    /// Per-file status of a worktree, optionally including ignored files
+    fn status(&self, _worktree_path: &Path, _include_ignored: bool) -> GitResult<Vec<FileStatus>> {
+        Err(GitError::Unsupported("status"))
+    }
+
+    /// Untracked and ignored paths matching any of the glob `patterns`, relative to the
+    /// worktree root. A directory with nothing tracked inside is listed once, with a
+    /// trailing `/`, rather than file by file.
+    fn untracked_matching(
+        &self,
+        _worktree_path: &Path,
+        _patterns: &[String],
+    ) -> GitResult<Vec<String>> {
+        Err(GitError::Unsupported("untracked_matching"))
+    }
+
+    /// Content of an unmerged file at index stage 1 (base), 2 (ours) or 3 (theirs),
+    /// or `None` when that side has no such file
+    fn conflict_stage(
+        &self,
+        _worktree_path: &Path,
+        _path: &str,
+        _stage: u8,
+    ) -> GitResult<Option<Vec<u8>>> {
+        Err(GitError::Unsupported("conflict_stage"))
+    }
+
+    /// Stage whole files, including deletions
+    fn stage(&self, _worktree_path: &Path, _paths: &[String]) -> GitResult<()> {
+        Err(GitError::Unsupported("stage"))
+    }
+
+    /// Reset files in the index to HEAD, leaving the working tree alone
+    fn unstage(&self, _worktree_path: &Path, _paths: &[String]) -> GitResult<()> {
+        Err(GitError::Unsupported("unstage"))
+    }
+
+    /// Record untracked files in the index with empty content (`git add -N`)
+    fn intent_to_add(&self, _worktree_path: &Path, _paths: &[String]) -> GitResult<()> {
+        Err(GitError::Unsupported("intent_to_add"))
+    }
+
+    /// Apply a unified diff to the index only, optionally in reverse
+    fn apply_to_index(&self, _worktree_path: &Path, _patch: &str, _reverse: bool) -> GitResult<()> {
+        Err(GitError::Unsupported("apply_to_index"))
+    }
+
+    /// Commit the index. Without a message, `amend` keeps the existing one.
+    fn commit(
+        &self,
+        _worktree_path: &Path,
+        _message: Option<&str>,
+        _amend: bool,
+        _sign_off: bool,
+    ) -> GitResult<()> {
+        Err(GitError::Unsupported("commit"))
+    }
+
+    /// Summary of the commit `rev` resolves to
+    fn commit_info(&self, _repo_path: &Path, _rev: &str) -> GitResult<CommitInfo> {
+        Err(GitError::Unsupported("commit_info"))
+    }
+
+    /// Up to `max_count` commits reachable from `revisions` (anything `git log`
+    /// accepts, e.g. `main`, `^base` or `base..feature`), children before parents
+    fn log(
+        &self,
+        _repo_path: &Path,
+        _revisions: &[String],
+        _max_count: usize,
+    ) -> GitResult<Vec<CommitInfo>> {
+        Err(GitError::Unsupported("log"))
+    }
+
+    /// Short names of the branches, remote branches and tags pointing at each commit
+    fn commit_refs(&self, _repo_path: &Path) -> GitResult<HashMap<String, Vec<String>>> {
+        Err(GitError::Unsupported("commit_refs"))
+    }
+
+    /// Whether the worktree has staged, unstaged or untracked changes
+    fn is_dirty(&self, _worktree_path: &Path) -> GitResult<bool> {
+        Err(GitError::Unsupported("is_dirty"))
+    }
+
+    /// Structured diff between the two sides described by `target`, limited to
+    /// `paths` unless empty
+    fn diff(
+        &self,
+        _worktree_path: &Path,
+        _target: &DiffTarget,
+        _paths: &[String],
+        _include_content: bool,
+    ) -> GitResult<Vec<FileDiff>> {
+        Err(GitError::Unsupported("diff"))
+    }
+
+    /// Lines added and removed per file between two commits or trees, with renames
+    fn diff_stat(&self, _repo_path: &Path, _from: &str, _to: &str) -> GitResult<Vec<FileStat>> {
+        Err(GitError::Unsupported("diff_stat"))
+    }
+
+    /// Content of `path` in the commit or tree `rev`; `None` if it has no such file
+    fn blob_at(&self, _repo_path: &Path, _rev: &str, _path: &str) -> GitResult<Option<Vec<u8>>> {
+        Err(GitError::Unsupported("blob_at"))
+    }
+
+    /// Short name of the branch's configured upstream (`<name>@{upstream}`), if any
+    fn upstream(&self, _repo_path: &Path, _branch_name: &str) -> GitResult<Option<String>> {
+        Err(GitError::Unsupported("upstream"))
+    }
+
+    /// Local branches, then remote-tracking branches, each sorted by name. Leaves
+    /// `worktree_path` empty.
+    fn list_branches(&self, _repo_path: &Path) -> GitResult<Vec<Branch>> {
+        Err(GitError::Unsupported("list_branches"))
+    }
+
+    /// Ahead/behind counts and merge base of `left` against `right` for each pair
+    fn divergence(
+        &self,
+        _repo_path: &Path,
+        _pairs: &[(String, String)],
+    ) -> GitResult<Vec<Divergence>> {
+        Err(GitError::Unsupported("divergence"))
+    }
+
+    /// Commit `branch` forked from `upstream` at, found through the reflog of `upstream`
+    /// when it has been rewritten since; the plain merge base when the reflog can't tell
+    fn fork_point(&self, _repo_path: &Path, _upstream: &str, _branch: &str) -> GitResult<String> {
+        Err(GitError::Unsupported("fork_point"))
+    }
+
+    /// Commits reachable only from `left` and only from `right`
+    fn ahead_behind(
+        &self,
+        _repo_path: &Path,
+        _left: &str,
+        _right: &str,
+    ) -> GitResult<(usize, usize)> {
+        Err(GitError::Unsupported("ahead_behind"))
+    }
+
+    /// Create the local branch `branch_name` at `target`, failing if it exists
+    fn create_branch(&self, _repo_path: &Path, _branch_name: &str, _target: &str) -> GitResult<()> {
+        Err(GitError::Unsupported("create_branch"))
+    }
+
+    /// Rename a local branch, carrying its config and reflog along and updating any
+    /// worktree that has it checked out
+    fn rename_branch(&self, _repo_path: &Path, _old_name: &str, _new_name: &str) -> GitResult<()> {
+        Err(GitError::Unsupported("rename_branch"))
+    }
+
+    /// Delete a local branch whether or not it is merged
+    fn delete_branch(&self, _repo_path: &Path, _branch_name: &str) -> GitResult<()> {
+        Err(GitError::Unsupported("delete_branch"))
+    }
+
+    /// Point the full ref `refname` at commit `target`, creating it if needed
+    fn update_ref(&self, _repo_path: &Path, _refname: &str, _target: &str) -> GitResult<()> {
+        Err(GitError::Unsupported("update_ref"))
+    }
+
+    fn delete_ref(&self, _repo_path: &Path, _refname: &str) -> GitResult<()> {
+        Err(GitError::Unsupported("delete_ref"))
+    }
+
+    /// Full names of the refs under `prefix` (e.g. `refs/tags/`) with the commit each
+    /// points at, sorted by name
+    fn list_refs(&self, _repo_path: &Path, _prefix: &str) -> GitResult<Vec<(String, CommitInfo)>> {
+        Err(GitError::Unsupported("list_refs"))
+    }
+
+    /// Full names of the refs under `prefix` with the id of the object each points at,
+    /// sorted by name. Cheaper than `list_refs` when only changes matter.
+    fn ref_targets(&self, _repo_path: &Path, _prefix: &str) -> GitResult<Vec<(String, String)>> {
+        Err(GitError::Unsupported("ref_targets"))
+    }
+
+    /// Fetch `remote`, or every remote when `None`, optionally pruning remote-tracking
+    /// branches that no longer exist there
+    fn fetch(
+        &self,
+        _repo_path: &Path,
+        _remote: Option<&str>,
+        _prune: bool,
+        _transfer: &Transfer,
+    ) -> GitResult<()> {
+        Err(GitError::Unsupported("fetch"))
+    }
+
+    /// Fetch and integrate the upstream of the branch checked out in `worktree_path`,
+    /// by rebasing onto it or merging it
+    fn pull(
+        &self,
+        _worktree_path: &Path,
+        _rebase: bool,
+        _transfer: &Transfer,
+    ) -> GitResult<MergeResult> {
+        Err(GitError::Unsupported("pull"))
+    }
+
+    /// Push the branch checked out in `worktree_path` to the branch of the same name on
+    /// `remote`. `force_with_lease` only overwrites what the remote-tracking branch has seen.
+    fn push(
+        &self,
+        _worktree_path: &Path,
+        _remote: &str,
+        _set_upstream: bool,
+        _force_with_lease: bool,
+        _transfer: &Transfer,
+    ) -> GitResult<()> {
+        Err(GitError::Unsupported("push"))
+    }
+
+    /// Stash commits of the repository, `stash@{0}` first. All worktrees share one stash.
+    fn list_stashes(&self, _repo_path: &Path) -> GitResult<Vec<CommitInfo>> {
+        Err(GitError::Unsupported("list_stashes"))
+    }
+
+    /// Stash the changes to `paths` (all changes if empty). Returns whether a stash was
+    /// created, i.e. whether there was anything to stash.
+    fn stash_push(
+        &self,
+        _worktree_path: &Path,
+        _message: Option<&str>,
+        _include_untracked: bool,
+        _paths: &[String],
+    ) -> GitResult<bool> {
+        Err(GitError::Unsupported("stash_push"))
+    }
+
+    /// Apply the stash commit `stash` to the worktree, leaving conflicts to resolve
+    fn stash_apply(&self, _worktree_path: &Path, _stash: &str) -> GitResult<MergeResult> {
+        Err(GitError::Unsupported("stash_apply"))
+    }
+
+    /// Drop a stash by its reflog name, e.g. `stash@{2}`
+    fn stash_drop(&self, _repo_path: &Path, _stash: &str) -> GitResult<()> {
+        Err(GitError::Unsupported("stash_drop"))
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref CLI: CliBackend = CliBackend;
+    static ref PRIMARY: Box<dyn GitBackend> = select_backend();
+}
+
+/// Pick the primary backend. `CODECRAFT_GIT_BACKEND=cli` forces the git CLI.
+fn select_backend() -> Box<dyn GitBackend> {
+    match std::env::var("CODECRAFT_GIT_BACKEND").as_deref() {
+        Ok("cli") => Box::new(CliBackend),
+        _ => Box::new(NativeBackend),
+    }
+}
+
+/// Run `op` against the primary backend, retrying on the git CLI if it is unsupported there
+pub fn with_backend<T>(op: impl Fn(&dyn GitBackend) -> GitResult<T>) -> GitResult<T> {
+    match op(PRIMARY.as_ref()) {
+        Err(GitError::Unsupported(_)) if PRIMARY.name() != CLI.name() => op(&*CLI),
+        result => result,
+    }
+}
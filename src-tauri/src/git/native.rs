@@ -0,0 +1,636 @@
+use super::{GitBackend, GitError, GitResult};
+use crate::commands::branch::Branch;
+use crate::commands::commit::CommitInfo;
+use crate::commands::diff::{DiffHunk, DiffLine, DiffLineKind, DiffTarget, FileDiff, FileStat};
+use crate::commands::divergence::Divergence;
+use crate::commands::status::FileChange;
+use crate::commands::worktree::Worktree;
+use git2::{
+    BranchType, Delta, Diff, DiffFindOptions, DiffOptions, ErrorCode, Patch, ReferenceType,
+    Repository, Sort, StatusOptions, WorktreeLockStatus,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// In-process backend built on libgit2
+pub struct NativeBackend;
+
+impl From<git2::Error> for GitError {
+    fn from(error: git2::Error) -> Self {
+        GitError::Failed(error.message().to_string())
+    }
+}
+
+/// Open the repository containing `path`. Anything libgit2 cannot open (unknown
+/// extensions, reftable, ...) is reported as unsupported so the CLI gets a chance.
+fn open(path: &Path) -> GitResult<Repository> {
+    Repository::discover(path).map_err(|_| GitError::Unsupported("open repository"))
+}
+
+fn path_string(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    path.trim_end_matches('/').to_string()
+}
+
+/// Describe the checkout of a single worktree the way `git worktree list` does
+fn describe(repo: &Repository, path: String, is_main: bool) -> GitResult<Worktree> {
+    let head_ref = repo.find_reference("HEAD")?;
+    let is_detached = head_ref.symbolic_target().is_none();
+
+    let branch = head_ref
+        .symbolic_target()
+        .map(|target| target.trim_start_matches("refs/heads/").to_string());
+
+    let head = match repo.head() {
+        Ok(head) => head.peel_to_commit()?.id().to_string(),
+        Err(e) if e.code() == ErrorCode::UnbornBranch => git2::Oid::zero().to_string(),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(Worktree {
+        path,
+        head,
+        branch: if repo.is_bare() { None } else { branch },
+        is_bare: repo.is_bare(),
+        is_detached,
+        is_main,
+        name: None,
+        is_locked: false,
+        lock_reason: None,
+        is_prunable: false,
+        prunable_reason: None,
+    })
+}
+
+fn commit_info(commit: &git2::Commit) -> CommitInfo {
+    let author = commit.author();
+
+    CommitInfo {
+        sha: commit.id().to_string(),
+        parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+        summary: commit.summary().unwrap_or_default().to_string(),
+        body: commit.body().unwrap_or_default().trim_end().to_string(),
+        author_name: author.name().unwrap_or_default().to_string(),
+        author_email: author.email().unwrap_or_default().to_string(),
+        author_time: author.when().seconds(),
+        commit_time: commit.time().seconds(),
+    }
+}
+
+/// Short name of the upstream configured for the local branch `refname`
+fn upstream_of(repo: &Repository, refname: &str) -> GitResult<Option<String>> {
+    match repo.branch_upstream_name(refname) {
+        Ok(name) => {
+            let name = String::from_utf8_lossy(&name).to_string();
+            let short = name
+                .strip_prefix("refs/remotes/")
+                .or_else(|| name.strip_prefix("refs/heads/"))
+                .unwrap_or(&name);
+            Ok(Some(short.to_string()))
+        }
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Tree of `rev`, or `None` for an unborn HEAD
+fn tree_of<'r>(repo: &'r Repository, rev: &str) -> GitResult<Option<git2::Tree<'r>>> {
+    match repo.revparse_single(rev) {
+        Ok(object) => Ok(Some(object.peel_to_tree()?)),
+        Err(e) if rev == "HEAD" && e.code() == ErrorCode::UnbornBranch => Ok(None),
+        Err(e) if rev == "HEAD" && e.code() == ErrorCode::NotFound => Ok(None),
+        Err(_) => Err(GitError::Failed(format!("Unknown revision: {}", rev))),
+    }
+}
+
+fn file_change(status: Delta) -> FileChange {
+    match status {
+        Delta::Added | Delta::Untracked => FileChange::Added,
+        Delta::Deleted => FileChange::Deleted,
+        Delta::Renamed => FileChange::Renamed,
+        Delta::Copied => FileChange::Copied,
+        Delta::Typechange => FileChange::TypeChanged,
+        _ => FileChange::Modified,
+    }
+}
+
+fn lossy(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).to_string()
+}
+
+/// Convert a libgit2 diff into `FileDiff`s. `new_on_disk` means the new side is the
+/// working tree, so its content is read from disk rather than the object database.
+fn file_diffs(
+    repo: &Repository,
+    diff: &Diff,
+    new_on_disk: bool,
+    include_content: bool,
+) -> GitResult<Vec<FileDiff>> {
+    let mut files = Vec::new();
+
+    for index in 0..diff.deltas().len() {
+        let Some(delta) = diff.get_delta(index) else {
+            continue;
+        };
+        let patch = Patch::from_diff(diff, index)?;
+        let is_binary = delta.flags().is_binary()
+            || patch
+                .as_ref()
+                .is_some_and(|patch| patch.delta().flags().is_binary());
+
+        let old_file = delta.old_file();
+        let new_file = delta.new_file();
+        let blob_id = |file: &git2::DiffFile| {
+            (file.exists() && !file.id().is_zero()).then(|| file.id().to_string())
+        };
+
+        let mut hunks = Vec::new();
+        if let Some(patch) = patch.as_ref().filter(|_| !is_binary) {
+            for hunk_index in 0..patch.num_hunks() {
+                let (hunk, line_count) = patch.hunk(hunk_index)?;
+                let mut lines = Vec::with_capacity(line_count);
+
+                for line_index in 0..line_count {
+                    let line = patch.line_in_hunk(hunk_index, line_index)?;
+                    let kind = match line.origin() {
+                        '+' => DiffLineKind::Added,
+                        '-' => DiffLineKind::Removed,
+                        ' ' => DiffLineKind::Context,
+                        // "\ No newline at end of file" markers
+                        _ => continue,
+                    };
+                    lines.push(DiffLine {
+                        kind,
+                        content: lossy(line.content()),
+                        old_line: line.old_lineno(),
+                        new_line: line.new_lineno(),
+                    });
+                }
+
+                hunks.push(DiffHunk {
+                    header: lossy(hunk.header()).trim_end().to_string(),
+                    old_start: hunk.old_start(),
+                    old_lines: hunk.old_lines(),
+                    new_start: hunk.new_start(),
+                    new_lines: hunk.new_lines(),
+                    lines,
+                });
+            }
+        }
+
+        let (old_content, new_content) = if include_content && !is_binary {
+            let from_blob = |file: &git2::DiffFile| -> Option<String> {
+                let blob = repo.find_blob(file.id()).ok()?;
+                Some(lossy(blob.content()))
+            };
+            let old_content = old_file.exists().then(|| from_blob(&old_file)).flatten();
+            let new_content = if !new_file.exists() {
+                None
+            } else if new_on_disk {
+                let workdir = repo.workdir().unwrap_or(Path::new(""));
+                new_file
+                    .path()
+                    .and_then(|path| std::fs::read(workdir.join(path)).ok())
+                    .map(|bytes| lossy(&bytes))
+            } else {
+                from_blob(&new_file)
+            };
+            (old_content, new_content)
+        } else {
+            (None, None)
+        };
+
+        let path_string = |file: &git2::DiffFile| {
+            file.exists()
+                .then(|| file.path().map(|path| path.to_string_lossy().to_string()))
+                .flatten()
+        };
+
+        files.push(FileDiff {
+            old_path: path_string(&old_file),
+            new_path: path_string(&new_file),
+            change: file_change(delta.status()),
+            is_binary,
+            old_blob: blob_id(&old_file),
+            new_blob: if new_on_disk {
+                None
+            } else {
+                blob_id(&new_file)
+            },
+            old_content,
+            new_content,
+            hunks,
+        });
+    }
+
+    Ok(files)
+}
+
+impl GitBackend for NativeBackend {
+    fn name(&self) -> &'static str {
+        "native"
+    }
+
+    fn list_worktrees(&self, repo_path: &Path) -> GitResult<Vec<Worktree>> {
+        let repo = open(repo_path)?;
+        let main = Repository::open(repo.commondir())?;
+
+        let main_path = match main.workdir() {
+            Some(workdir) => path_string(workdir),
+            None => path_string(main.path()),
+        };
+        let mut worktrees = vec![describe(&main, main_path, true)?];
+
+        let mut linked = Vec::new();
+        for name in main.worktrees()?.iter().flatten() {
+            let worktree = main.find_worktree(name)?;
+            let path = path_string(worktree.path());
+
+            // Worktrees whose directory is gone can't be opened; report what git knows
+            let mut described = match Repository::open_from_worktree(&worktree) {
+                Ok(repo) => describe(&repo, path, false)?,
+                Err(_) => Worktree {
+                    path,
+                    head: git2::Oid::zero().to_string(),
+                    branch: None,
+                    is_bare: false,
+                    is_detached: false,
+                    is_main: false,
+                    name: None,
+                    is_locked: false,
+                    lock_reason: None,
+                    is_prunable: false,
+                    prunable_reason: None,
+                },
+            };
+
+            if let WorktreeLockStatus::Locked(reason) = worktree.is_locked()? {
+                described.is_locked = true;
+                described.lock_reason = reason
+                    .map(|reason| reason.trim_end().to_string())
+                    .filter(|reason| !reason.is_empty());
+            } else if let Err(e) = worktree.validate() {
+                // Like git, a locked worktree is never prunable
+                described.is_prunable = true;
+                described.prunable_reason = Some(e.message().to_string());
+            }
+
+            described.name = Some(name.to_string());
+            linked.push(described);
+        }
+
+        // Match `git worktree list`, which sorts linked worktrees by path
+        linked.sort_by(|a, b| a.path.cmp(&b.path));
+        worktrees.extend(linked);
+
+        Ok(worktrees)
+    }
+
+    fn common_dir(&self, path: &Path) -> GitResult<PathBuf> {
+        let repo = open(path)?;
+        repo.commondir()
+            .canonicalize()
+            .map_err(|e| GitError::Failed(format!("Failed to resolve path: {}", e)))
+    }
+
+    fn git_dir(&self, path: &Path) -> GitResult<PathBuf> {
+        let repo = open(path)?;
+        repo.path()
+            .canonicalize()
+            .map_err(|e| GitError::Failed(format!("Failed to resolve path: {}", e)))
+    }
+
+    fn resolve_commit(&self, repo_path: &Path, rev: &str) -> GitResult<String> {
+        let repo = open(repo_path)?;
+        let commit = repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|_| GitError::Failed(format!("Unknown revision: {}", rev)))?;
+
+        Ok(commit.id().to_string())
+    }
+
+    fn commit_info(&self, repo_path: &Path, rev: &str) -> GitResult<CommitInfo> {
+        let repo = open(repo_path)?;
+        let commit = repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|_| GitError::Failed(format!("Unknown revision: {}", rev)))?;
+
+        Ok(commit_info(&commit))
+    }
+
+    fn log(
+        &self,
+        repo_path: &Path,
+        revisions: &[String],
+        max_count: usize,
+    ) -> GitResult<Vec<CommitInfo>> {
+        let repo = open(repo_path)?;
+        let mut walk = repo.revwalk()?;
+        walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+
+        for revision in revisions {
+            let unknown = |_| GitError::Failed(format!("Unknown revision: {}", revision));
+            if revision.contains("...") {
+                // Symmetric differences are left to the CLI
+                return Err(GitError::Unsupported("log"));
+            } else if revision.contains("..") {
+                walk.push_range(revision).map_err(unknown)?;
+            } else if let Some(hidden) = revision.strip_prefix('^') {
+                let commit = repo
+                    .revparse_single(hidden)
+                    .and_then(|o| o.peel_to_commit());
+                walk.hide(commit.map_err(unknown)?.id())?;
+            } else {
+                let commit = repo
+                    .revparse_single(revision)
+                    .and_then(|o| o.peel_to_commit());
+                walk.push(commit.map_err(unknown)?.id())?;
+            }
+        }
+
+        walk.take(max_count)
+            .map(|id| Ok(commit_info(&repo.find_commit(id?)?)))
+            .collect()
+    }
+
+    fn commit_refs(&self, repo_path: &Path) -> GitResult<HashMap<String, Vec<String>>> {
+        let repo = open(repo_path)?;
+        let mut listed = Vec::new();
+
+        for reference in repo.references()? {
+            let reference = reference?;
+            // Skip `origin/HEAD` and the like, which only point at another ref
+            if reference.kind() == Some(ReferenceType::Symbolic) {
+                continue;
+            }
+            let (Some(name), Some(short)) = (reference.name(), reference.shorthand()) else {
+                continue;
+            };
+            if !["refs/heads/", "refs/remotes/", "refs/tags/"]
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+            {
+                continue;
+            }
+            if let Ok(commit) = reference.peel_to_commit() {
+                listed.push((name.to_string(), short.to_string(), commit.id().to_string()));
+            }
+        }
+
+        // Match the order of `git for-each-ref`
+        listed.sort();
+
+        let mut refs: HashMap<String, Vec<String>> = HashMap::new();
+        for (_, short, commit) in listed {
+            refs.entry(commit).or_default().push(short);
+        }
+
+        Ok(refs)
+    }
+
+    fn ref_targets(&self, repo_path: &Path, prefix: &str) -> GitResult<Vec<(String, String)>> {
+        let repo = open(repo_path)?;
+        let mut refs = Vec::new();
+
+        for reference in repo.references()? {
+            let reference = reference?;
+            let Some(name) = reference.name().filter(|name| name.starts_with(prefix)) else {
+                continue;
+            };
+            // Symbolic refs are listed with the object they resolve to, as by git
+            if let Some(target) = reference
+                .resolve()
+                .ok()
+                .and_then(|resolved| resolved.target())
+            {
+                refs.push((name.to_string(), target.to_string()));
+            }
+        }
+
+        refs.sort();
+        Ok(refs)
+    }
+
+    fn conflict_stage(
+        &self,
+        worktree_path: &Path,
+        path: &str,
+        stage: u8,
+    ) -> GitResult<Option<Vec<u8>>> {
+        let repo = open(worktree_path)?;
+        let index = repo.index()?;
+        let Some(entry) = index.get_path(Path::new(path), stage as i32) else {
+            return Ok(None);
+        };
+
+        let blob = repo.find_blob(entry.id)?;
+        Ok(Some(blob.content().to_vec()))
+    }
+
+    fn is_dirty(&self, worktree_path: &Path) -> GitResult<bool> {
+        let repo = open(worktree_path)?;
+        let mut options = StatusOptions::new();
+        options.include_untracked(true).include_ignored(false);
+
+        let statuses = repo.statuses(Some(&mut options))?;
+        Ok(!statuses.is_empty())
+    }
+
+    fn upstream(&self, repo_path: &Path, branch_name: &str) -> GitResult<Option<String>> {
+        let repo = open(repo_path)?;
+        upstream_of(&repo, &format!("refs/heads/{}", branch_name))
+    }
+
+    fn list_branches(&self, repo_path: &Path) -> GitResult<Vec<Branch>> {
+        let repo = open(repo_path)?;
+        let mut listed = Vec::new();
+
+        for branch in repo.branches(None)? {
+            let (branch, kind) = branch?;
+            let reference = branch.get();
+            // Skip `origin/HEAD` and the like, which only point at another ref
+            if reference.kind() == Some(ReferenceType::Symbolic) {
+                continue;
+            }
+            let (Some(name), Some(short)) = (reference.name(), reference.shorthand()) else {
+                continue;
+            };
+            let Ok(commit) = reference.peel_to_commit() else {
+                continue;
+            };
+
+            let is_remote = kind == BranchType::Remote;
+            let upstream = if is_remote {
+                None
+            } else {
+                upstream_of(&repo, name)?
+            };
+
+            listed.push((
+                name.to_string(),
+                Branch {
+                    name: short.to_string(),
+                    is_remote,
+                    upstream,
+                    worktree_path: None,
+                    last_commit: commit_info(&commit),
+                },
+            ));
+        }
+
+        // Match the order of `git for-each-ref`
+        listed.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(listed.into_iter().map(|(_, branch)| branch).collect())
+    }
+
+    fn diff(
+        &self,
+        worktree_path: &Path,
+        target: &DiffTarget,
+        paths: &[String],
+        include_content: bool,
+    ) -> GitResult<Vec<FileDiff>> {
+        let repo = open(worktree_path)?;
+        let mut options = DiffOptions::new();
+        for path in paths {
+            options.pathspec(path);
+        }
+        options.disable_pathspec_match(!paths.is_empty());
+
+        let (mut diff, new_on_disk) = match target {
+            DiffTarget::WorkingTree => {
+                options
+                    .include_untracked(true)
+                    .recurse_untracked_dirs(true)
+                    .show_untracked_content(true);
+                (repo.diff_index_to_workdir(None, Some(&mut options))?, true)
+            }
+            DiffTarget::Staged => {
+                let head = tree_of(&repo, "HEAD")?;
+                (
+                    repo.diff_tree_to_index(head.as_ref(), None, Some(&mut options))?,
+                    false,
+                )
+            }
+            DiffTarget::Parent { parent } => {
+                let parent = parent
+                    .as_deref()
+                    .ok_or(GitError::Failed("No parent branch given".to_string()))?;
+                let head = repo.head()?.peel_to_commit()?.id();
+                let parent = repo.revparse_single(parent)?.peel_to_commit()?.id();
+                let base = repo.find_commit(repo.merge_base(head, parent)?)?.tree()?;
+
+                options
+                    .include_untracked(true)
+                    .recurse_untracked_dirs(true)
+                    .show_untracked_content(true);
+                (
+                    repo.diff_tree_to_workdir_with_index(Some(&base), Some(&mut options))?,
+                    true,
+                )
+            }
+            DiffTarget::Refs { from, to } => {
+                let from = tree_of(&repo, from)?;
+                let to = tree_of(&repo, to)?;
+                (
+                    repo.diff_tree_to_tree(from.as_ref(), to.as_ref(), Some(&mut options))?,
+                    false,
+                )
+            }
+        };
+
+        diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+        file_diffs(&repo, &diff, new_on_disk, include_content)
+    }
+
+    fn diff_stat(&self, repo_path: &Path, from: &str, to: &str) -> GitResult<Vec<FileStat>> {
+        let repo = open(repo_path)?;
+        let from = tree_of(&repo, from)?;
+        let to = tree_of(&repo, to)?;
+        let mut diff = repo.diff_tree_to_tree(from.as_ref(), to.as_ref(), None)?;
+        diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+
+        let mut stats = Vec::new();
+        for index in 0..diff.deltas().len() {
+            let Some(delta) = diff.get_delta(index) else {
+                continue;
+            };
+            let patch = Patch::from_diff(&diff, index)?;
+            let is_binary = delta.flags().is_binary()
+                || patch
+                    .as_ref()
+                    .is_some_and(|patch| patch.delta().flags().is_binary());
+            let (_, insertions, deletions) = match patch.as_ref().filter(|_| !is_binary) {
+                Some(patch) => patch.line_stats()?,
+                None => (0, 0, 0),
+            };
+
+            let path = |file: git2::DiffFile| file.path().map(path_string);
+            let old_path = path(delta.old_file());
+            let new_path = path(delta.new_file());
+            stats.push(FileStat {
+                path: new_path.or_else(|| old_path.clone()).unwrap_or_default(),
+                old_path: old_path.filter(|_| delta.status() == Delta::Renamed),
+                insertions,
+                deletions,
+                is_binary,
+            });
+        }
+
+        Ok(stats)
+    }
+
+    fn blob_at(&self, repo_path: &Path, rev: &str, path: &str) -> GitResult<Option<Vec<u8>>> {
+        let repo = open(repo_path)?;
+        let Some(tree) = tree_of(&repo, rev)? else {
+            return Ok(None);
+        };
+        let entry = match tree.get_path(Path::new(path)) {
+            Ok(entry) => entry,
+            Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let object = entry.to_object(&repo)?;
+        Ok(object.as_blob().map(|blob| blob.content().to_vec()))
+    }
+
+    fn ahead_behind(&self, repo_path: &Path, left: &str, right: &str) -> GitResult<(usize, usize)> {
+        let repo = open(repo_path)?;
+        let left = repo.revparse_single(left)?.peel_to_commit()?.id();
+        let right = repo.revparse_single(right)?.peel_to_commit()?.id();
+
+        Ok(repo.graph_ahead_behind(left, right)?)
+    }
+
+    fn divergence(
+        &self,
+        repo_path: &Path,
+        pairs: &[(String, String)],
+    ) -> GitResult<Vec<Divergence>> {
+        // One repository, and so one object cache, for the whole batch
+        let repo = open(repo_path)?;
+
+        pairs
+            .iter()
+            .map(|(left, right)| {
+                let left = repo.revparse_single(left)?.peel_to_commit()?.id();
+                let right = repo.revparse_single(right)?.peel_to_commit()?.id();
+                let (ahead, behind) = repo.graph_ahead_behind(left, right)?;
+                let merge_base = match repo.merge_base(left, right) {
+                    Ok(base) => Some(base.to_string()),
+                    Err(e) if e.code() == ErrorCode::NotFound => None,
+                    Err(e) => return Err(e.into()),
+                };
+
+                Ok(Divergence {
+                    ahead,
+                    behind,
+                    merge_base,
+                })
+            })
+            .collect()
+    }
+}
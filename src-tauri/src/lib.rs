@@ -1,9 +1,34 @@
 mod commands;
+mod git;
 
+use commands::askpass::*;
+use commands::branch::*;
+use commands::carry::*;
+use commands::cherry_pick::*;
+use commands::commit::*;
+use commands::conflict::*;
+use commands::diff::*;
+use commands::divergence::*;
 use commands::filesystem::*;
+use commands::lineage::*;
+use commands::log::*;
+use commands::merge_preview::*;
+use commands::provision::*;
 use commands::pty::*;
+use commands::rebase::*;
+use commands::remote::*;
+use commands::restack::*;
+use commands::stash::*;
+use commands::status::*;
+use commands::watcher::*;
 use commands::worktree::*;
 
+/// When git runs this executable as its askpass helper, answer the prompt and return
+/// the exit code; `None` when running as the app
+pub fn askpass() -> Option<i32> {
+    commands::askpass::helper()
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -15,9 +40,84 @@ pub fn run() {
             get_main_repo_path,
             create_worktree,
             remove_worktree,
+            lock_worktree,
+            unlock_worktree,
+            prune_worktrees,
+            repair_worktrees,
+            move_worktree,
+            rename_worktree_branch,
             merge_branch,
             has_uncommitted_changes,
             get_branch_info,
+            // Branch commands
+            list_branches,
+            list_archived_branches,
+            restore_archived_branch,
+            delete_archived_branch,
+            // Status commands
+            get_worktree_status,
+            // Diff commands
+            get_diff,
+            // Commit commands
+            stage_files,
+            unstage_files,
+            stage_lines,
+            unstage_lines,
+            commit_changes,
+            // Conflict commands
+            get_conflicts,
+            accept_conflict_side,
+            save_conflict_resolution,
+            mark_resolved,
+            conclude_merge,
+            abort_merge,
+            // Log commands
+            get_commit_log,
+            // Provisioning commands
+            get_provision_recipe,
+            set_provision_recipe,
+            run_provisioning,
+            cancel_provisioning,
+            get_provision_status,
+            // Carry commands
+            get_carry_config,
+            set_carry_config,
+            carry_files,
+            // Watcher commands
+            watch_repository,
+            unwatch_repository,
+            // Lineage commands
+            get_worktree_graph,
+            set_worktree_parent,
+            get_ahead_behind_matrix,
+            // Merge preview commands
+            preview_merge,
+            // Rebase commands
+            rebase_worktree,
+            continue_rebase,
+            skip_rebase,
+            abort_rebase,
+            // Restack commands
+            restack,
+            continue_restack,
+            abort_restack,
+            // Remote commands
+            fetch_remote,
+            pull_branch,
+            push_branch,
+            answer_credential_prompt,
+            // Cherry-pick commands
+            cherry_pick,
+            continue_cherry_pick,
+            skip_cherry_pick,
+            abort_cherry_pick,
+            // Stash commands
+            list_stashes,
+            push_stash,
+            apply_stash,
+            pop_stash,
+            drop_stash,
+            transfer_stash,
             // Filesystem commands
             read_directory,
             read_file,
@@ -2,5 +2,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // Git starts this executable to ask for credentials; answer before any UI comes up
+    if let Some(code) = codecraft_lib::askpass() {
+        std::process::exit(code);
+    }
     codecraft_lib::run()
 }
@@ -2,8 +2,30 @@ import { useEffect } from "react";
 import { ReactFlowProvider } from "@xyflow/react";
 import { WorktreeCanvas } from "@/features/worktree-graph/components/WorktreeCanvas";
 import { WorkspaceView } from "@/features/workspace";
-import { Sidebar } from "@/components/layout";
+import { Sidebar, CredentialPromptModal } from "@/components/layout";
 import { useProjectStore, useWorktreeStore, useUIStore } from "@/store";
+import { isTauri } from "@/lib/environment";
+import {
+  watchRepository,
+  unwatchRepository,
+  onRepositoryEvent,
+  type RepositoryEvent,
+} from "@/lib/tauri";
+
+/**
+ * Whether an event changes what the graph shows. Of the refs only branch tips
+ * do; remote-tracking, stash and archive refs move on every fetch or stash.
+ */
+function changesGraph(event: RepositoryEvent): boolean {
+  switch (event.kind) {
+    case "status-changed":
+      return false;
+    case "refs-changed":
+      return event.refs.some((ref) => ref.ref_name.startsWith("refs/heads/"));
+    default:
+      return true;
+  }
+}
 
 function App() {
   const { activeProjectId, getActiveProject } = useProjectStore();
@@ -18,6 +40,25 @@ function App() {
     }
   }, [activeProjectId, getActiveProject, fetchWorktrees]);
 
+  // Refresh the graph when branches or worktrees change outside the app
+  useEffect(() => {
+    const project = getActiveProject();
+    if (!project || !isTauri) return;
+
+    watchRepository(project.path).catch(console.error);
+    // Kept as a promise so a cleanup that runs before it resolves still unlistens
+    const unlisten = onRepositoryEvent((event) => {
+      if (event.repo_path === project.path && changesGraph(event)) {
+        fetchWorktrees();
+      }
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+      unwatchRepository(project.path).catch(console.error);
+    };
+  }, [activeProjectId, getActiveProject, fetchWorktrees]);
+
   // Find the open worktree
   const openWorktree = worktrees.find((wt) => wt.id === openWorktreeId);
 
@@ -38,6 +79,8 @@ function App() {
           </ReactFlowProvider>
         )}
       </div>
+
+      <CredentialPromptModal />
     </div>
   );
 }
@@ -0,0 +1,74 @@
+import { useEffect, useState } from "react";
+import type { UnlistenFn } from "@tauri-apps/api/event";
+import { Modal, Button, Input } from "@/components/ui";
+import { isTauri } from "@/lib/environment";
+import {
+  answerCredentialPrompt,
+  onCredentialPrompt,
+  type CredentialPrompt,
+} from "@/lib/tauri";
+
+// Answers the username, password and passphrase prompts of fetch, pull and push
+export function CredentialPromptModal() {
+  const [prompts, setPrompts] = useState<CredentialPrompt[]>([]);
+  const [response, setResponse] = useState("");
+
+  useEffect(() => {
+    if (!isTauri) return;
+
+    let unlisten: UnlistenFn | undefined;
+    onCredentialPrompt((prompt) => {
+      setPrompts((queued) => [...queued, prompt]);
+    }).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => unlisten?.();
+  }, []);
+
+  const current = prompts[0];
+
+  const answer = (value: string | null) => {
+    if (!current) return;
+    answerCredentialPrompt(current.id, value).catch(console.error);
+    setPrompts((queued) => queued.slice(1));
+    setResponse("");
+  };
+
+  const handleSubmit = (e: React.FormEvent) => {
+    e.preventDefault();
+    answer(response);
+  };
+
+  return (
+    <Modal
+      isOpen={!!current}
+      onClose={() => answer(null)}
+      title="Authentication Required"
+    >
+      {current && (
+        <form onSubmit={handleSubmit} className="space-y-4">
+          <p className="text-sm text-text-secondary whitespace-pre-wrap break-words">
+            {current.prompt}
+          </p>
+
+          <Input
+            type={current.secret ? "password" : "text"}
+            value={response}
+            onChange={(e) => setResponse(e.target.value)}
+            autoFocus
+          />
+
+          <div className="flex justify-end gap-2 pt-2">
+            <Button type="button" variant="ghost" onClick={() => answer(null)}>
+              Cancel
+            </Button>
+            <Button type="submit" variant="primary">
+              Continue
+            </Button>
+          </div>
+        </form>
+      )}
+    </Modal>
+  );
+}
@@ -1 +1,2 @@
 export { Sidebar } from "./Sidebar";
+export { CredentialPromptModal } from "./CredentialPromptModal";
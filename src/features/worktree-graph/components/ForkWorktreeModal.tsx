@@ -3,6 +3,7 @@ import { homeDir } from "@tauri-apps/api/path";
 import { Modal, Button, Input } from "@/components/ui";
 import { useWorktreeStore, useProjectStore } from "@/store";
 import { isTauri } from "@/lib/environment";
+import { listBranches, type Branch } from "@/lib/tauri";
 
 interface ForkWorktreeModalProps {
   isOpen: boolean;
@@ -15,10 +16,13 @@ export function ForkWorktreeModal({
   onClose,
   baseBranch = "HEAD",
 }: ForkWorktreeModalProps) {
-  const { createNewWorktree, loading, error, clearError } = useWorktreeStore();
+  const { createNewWorktree, mainRepoPath, loading, error, clearError } =
+    useWorktreeStore();
   const { getActiveProject } = useProjectStore();
 
   const [branchName, setBranchName] = useState("");
+  const [selectedBase, setSelectedBase] = useState(baseBranch);
+  const [branches, setBranches] = useState<Branch[]>([]);
   const [homePath, setHomePath] = useState<string>("~");
   const [validationError, setValidationError] = useState<string | null>(null);
 
@@ -51,10 +55,19 @@ export function ForkWorktreeModal({
   useEffect(() => {
     if (isOpen) {
       setBranchName("");
+      setSelectedBase(baseBranch);
       setValidationError(null);
       clearError();
     }
-  }, [isOpen, clearError]);
+  }, [isOpen, baseBranch, clearError]);
+
+  // Load the branches to fork from
+  useEffect(() => {
+    if (!isOpen || !isTauri || !mainRepoPath) return;
+    listBranches(mainRepoPath)
+      .then(setBranches)
+      .catch(() => setBranches([]));
+  }, [isOpen, mainRepoPath]);
 
   const validateForm = useCallback(() => {
     if (!branchName.trim()) {
@@ -80,7 +93,7 @@ export function ForkWorktreeModal({
     if (!validateForm()) return;
 
     try {
-      await createNewWorktree(branchName, baseBranch, worktreePath);
+      await createNewWorktree(branchName, selectedBase, worktreePath);
       onClose();
     } catch {
       // Error is handled by the store
@@ -93,10 +106,36 @@ export function ForkWorktreeModal({
         <p className="text-sm text-text-secondary">
           Create a new worktree and branch from{" "}
           <span className="font-mono text-accent-primary">
-            {baseBranch || "HEAD"}
+            {selectedBase || "HEAD"}
           </span>
         </p>
 
+        {branches.length > 0 && (
+          <div className="flex flex-col gap-1.5">
+            <label
+              htmlFor="base-branch"
+              className="text-xs font-medium text-text-secondary"
+            >
+              Base Branch
+            </label>
+            <select
+              id="base-branch"
+              value={selectedBase}
+              onChange={(e) => setSelectedBase(e.target.value)}
+              className="h-9 w-full rounded-lg px-3 bg-bg-elevated text-text-primary border border-border-default font-mono text-sm focus:outline-none focus:ring-2 focus:ring-accent-primary"
+            >
+              {!branches.some((branch) => branch.name === selectedBase) && (
+                <option value={selectedBase}>{selectedBase}</option>
+              )}
+              {branches.map((branch) => (
+                <option key={branch.name} value={branch.name}>
+                  {branch.name} — {branch.last_commit.summary}
+                </option>
+              ))}
+            </select>
+          </div>
+        )}
+
         <Input
           label="Branch Name"
           placeholder="feature/my-new-feature"
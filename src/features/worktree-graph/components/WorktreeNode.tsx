@@ -67,7 +67,8 @@ export const WorktreeNode = memo(function WorktreeNode({
         selected
           ? "border-accent-primary shadow-lg shadow-glow-primary"
           : "border-border-default hover:border-border-default",
-        worktree.is_main && "ring-1 ring-accent-success/30"
+        worktree.is_main && "ring-1 ring-accent-success/30",
+        worktree.is_prunable && "opacity-60 border-dashed"
       )}
     >
       {/* Input handle */}
@@ -115,6 +116,22 @@ export const WorktreeNode = memo(function WorktreeNode({
             main
           </span>
         )}
+        {worktree.is_locked && (
+          <span
+            className="text-[10px] font-medium px-1.5 py-0.5 rounded bg-accent-warning/20 text-accent-warning"
+            title={worktree.lock_reason || "Locked"}
+          >
+            locked
+          </span>
+        )}
+        {worktree.is_prunable && (
+          <span
+            className="text-[10px] font-medium px-1.5 py-0.5 rounded bg-text-tertiary/20 text-text-tertiary"
+            title={worktree.prunable_reason || "Directory is missing"}
+          >
+            missing
+          </span>
+        )}
       </div>
 
       {/* Body */}
@@ -9,19 +9,343 @@ export interface Worktree {
   is_bare: boolean;
   is_detached: boolean;
   is_main: boolean;
+  name: string | null;
+  is_locked: boolean;
+  lock_reason: string | null;
+  is_prunable: boolean;
+  prunable_reason: string | null;
+}
+
+export interface LinkedWorktree extends Worktree {
+  parent_branch: string | null;
+  parent_path: string | null;
 }
 
 export interface MergeResult {
   success: boolean;
   message: string;
   conflicts: string[];
+  worktree_path: string | null;
+  commit: string | null;
+}
+
+export interface RemovedWorktree {
+  removed: boolean;
+  branch: string | null;
+  unmerged: CommitInfo[];
+  archive_ref: string | null;
+  branch_deleted: boolean;
 }
 
+export type CreateMode =
+  | "new-branch"
+  | "existing"
+  | "track"
+  | "orphan"
+  | "detached";
+
+export interface CreatedWorktree extends Worktree {
+  mode: CreateMode;
+  carried: string[];
+  provisioning: boolean;
+}
+
+export type MergeStrategy = "merge" | "squash" | "rebase" | "ff-only" | "no-ff";
+
 export interface BranchInfo {
   name: string;
   upstream: string | null;
   ahead: number;
   behind: number;
+  parent_branch: string | null;
+  parent_ahead: number;
+  parent_behind: number;
+  last_commit: CommitInfo | null;
+}
+
+export type FileChange =
+  | "added"
+  | "modified"
+  | "deleted"
+  | "renamed"
+  | "copied"
+  | "type_changed";
+
+export type ConflictKind =
+  | "both_modified"
+  | "both_added"
+  | "both_deleted"
+  | "added_by_us"
+  | "added_by_them"
+  | "deleted_by_us"
+  | "deleted_by_them";
+
+export interface SubmoduleState {
+  commit_changed: boolean;
+  has_tracked_changes: boolean;
+  has_untracked_changes: boolean;
+}
+
+export interface FileStatus {
+  path: string;
+  original_path: string | null;
+  staged: FileChange | null;
+  unstaged: FileChange | null;
+  untracked: boolean;
+  ignored: boolean;
+  conflict: ConflictKind | null;
+  submodule: SubmoduleState | null;
+}
+
+export type DiffTarget =
+  | { kind: "working_tree" }
+  | { kind: "staged" }
+  | { kind: "parent"; parent: string | null }
+  | { kind: "refs"; from: string; to: string };
+
+export interface DiffLine {
+  kind: "context" | "added" | "removed";
+  content: string;
+  old_line: number | null;
+  new_line: number | null;
+}
+
+export interface DiffHunk {
+  header: string;
+  old_start: number;
+  old_lines: number;
+  new_start: number;
+  new_lines: number;
+  lines: DiffLine[];
+}
+
+export interface FileDiff {
+  old_path: string | null;
+  new_path: string | null;
+  change: FileChange;
+  is_binary: boolean;
+  old_blob: string | null;
+  new_blob: string | null;
+  old_content: string | null;
+  new_content: string | null;
+  hunks: DiffHunk[];
+}
+
+export interface FileStat {
+  path: string;
+  old_path: string | null;
+  insertions: number;
+  deletions: number;
+  is_binary: boolean;
+}
+
+export interface CommitInfo {
+  sha: string;
+  parents: string[];
+  summary: string;
+  body: string;
+  author_name: string;
+  author_email: string;
+  author_time: number;
+  commit_time: number;
+}
+
+export interface Branch {
+  name: string;
+  is_remote: boolean;
+  upstream: string | null;
+  worktree_path: string | null;
+  last_commit: CommitInfo;
+}
+
+export interface ArchivedBranch {
+  ref_name: string;
+  branch: string;
+  archived_at: number;
+  commit: CommitInfo;
+}
+
+export type RestackStatus =
+  | "restacked"
+  | "up_to_date"
+  | "skipped"
+  | "conflicted"
+  | "failed";
+
+export interface BranchRestack {
+  branch: string;
+  parent: string;
+  status: RestackStatus;
+  worktree_path: string | null;
+  message: string;
+  conflicts: string[];
+  commit: string | null;
+}
+
+export interface RestackResult {
+  root: string;
+  success: boolean;
+  branches: BranchRestack[];
+  pending: string[];
+}
+
+export interface Divergence {
+  ahead: number;
+  behind: number;
+  merge_base: string | null;
+}
+
+export interface EdgeDivergence extends Divergence {
+  worktree_path: string;
+  parent_branch: string;
+  parent_path: string | null;
+}
+
+export interface StashEntry {
+  index: number;
+  branch: string | null;
+  message: string;
+  worktree_path: string | null;
+  commit: CommitInfo;
+}
+
+export interface GraphEdge {
+  from: number;
+  to: number;
+}
+
+export interface LogEntry extends CommitInfo {
+  refs: string[];
+  lane: number;
+  edges: GraphEdge[];
+}
+
+export interface LineRef {
+  old_line: number | null;
+  new_line: number | null;
+}
+
+export interface PatchSelection {
+  path: string;
+  hunks?: number[];
+  lines?: LineRef[];
+}
+
+export type ConflictSide = "ours" | "theirs";
+
+export interface ConflictFile {
+  path: string;
+  kind: ConflictKind;
+  is_binary: boolean;
+  base: string | null;
+  ours: string | null;
+  theirs: string | null;
+  working: string | null;
+}
+
+export interface ConflictHunk {
+  start_line: number;
+  ours: string;
+  base: string | null;
+  theirs: string;
+}
+
+export interface PredictedConflict {
+  path: string;
+  kind: ConflictKind;
+  is_binary: boolean;
+  hunks: ConflictHunk[];
+}
+
+export interface MergePreview {
+  source_branch: string;
+  target_branch: string;
+  up_to_date: boolean;
+  fast_forward: boolean;
+  clean: boolean;
+  merge_base: string | null;
+  conflicts: PredictedConflict[];
+  messages: string[];
+  files: FileStat[];
+  insertions: number;
+  deletions: number;
+}
+
+export type CarryMode = "copy" | "hardlink" | "reflink";
+
+export interface CarryRule {
+  pattern: string;
+  mode?: CarryMode;
+}
+
+export interface CarryConfig {
+  rules: CarryRule[];
+}
+
+export interface RefUpdate {
+  ref_name: string;
+  old: string | null;
+  new: string | null;
+}
+
+export type RepositoryEvent = { repo_path: string } & (
+  | { kind: "worktree-added"; worktree: Worktree }
+  | { kind: "worktree-removed"; path: string }
+  | {
+      kind: "head-moved";
+      worktree_path: string;
+      branch: string | null;
+      old_head: string;
+      new_head: string;
+    }
+  | { kind: "refs-changed"; refs: RefUpdate[] }
+  | { kind: "status-changed"; worktree_path: string }
+);
+
+export interface ProvisionStep {
+  name?: string | null;
+  run: string;
+}
+
+export interface ProvisionRecipe {
+  steps: ProvisionStep[];
+}
+
+export type ProvisionState = "running" | "succeeded" | "failed" | "cancelled";
+
+export interface ProvisionRecord {
+  state: ProvisionState;
+  started_at: number;
+  finished_at: number | null;
+  step: number | null;
+  message: string | null;
+}
+
+export type ProvisionEvent = { worktree_path: string } & (
+  | { kind: "step_started"; step: number; name: string }
+  | {
+      kind: "output";
+      step: number;
+      stream: "stdout" | "stderr";
+      data: string;
+    }
+  | { kind: "finished"; record: ProvisionRecord }
+);
+
+export type RemoteOperation = "fetch" | "pull" | "push";
+
+export interface RemoteProgress {
+  path: string;
+  operation: RemoteOperation;
+  message: string;
+  percent: number | null;
+}
+
+export interface CredentialPrompt {
+  id: number;
+  repo_path: string;
+  prompt: string;
+  secret: boolean;
 }
 
 // Worktree commands
@@ -36,34 +360,97 @@ export async function getMainRepoPath(currentPath: string): Promise<string> {
 export async function createWorktree(
   repoPath: string,
   worktreePath: string,
-  branchName: string,
-  baseBranch: string
-): Promise<Worktree> {
-  return invoke<Worktree>("create_worktree", {
+  branchName: string | null,
+  baseBranch: string | null,
+  mode?: CreateMode
+): Promise<CreatedWorktree> {
+  return invoke<CreatedWorktree>("create_worktree", {
     repoPath,
     worktreePath,
     branchName,
     baseBranch,
+    mode,
   });
 }
 
 export async function removeWorktree(
   repoPath: string,
   worktreePath: string,
-  force = false
+  force = false,
+  deleteBranch = false,
+  archive = false,
+  discardUnmerged = false,
+  discardChanges = false
+): Promise<RemovedWorktree> {
+  return invoke<RemovedWorktree>("remove_worktree", {
+    repoPath,
+    worktreePath,
+    force,
+    deleteBranch,
+    archive,
+    discardUnmerged,
+    discardChanges,
+  });
+}
+
+export async function lockWorktree(
+  repoPath: string,
+  worktreePath: string,
+  reason?: string
+): Promise<void> {
+  return invoke<void>("lock_worktree", { repoPath, worktreePath, reason });
+}
+
+export async function unlockWorktree(
+  repoPath: string,
+  worktreePath: string
 ): Promise<void> {
-  return invoke<void>("remove_worktree", { repoPath, worktreePath, force });
+  return invoke<void>("unlock_worktree", { repoPath, worktreePath });
+}
+
+export async function moveWorktree(
+  repoPath: string,
+  worktreePath: string,
+  newPath: string
+): Promise<Worktree> {
+  return invoke<Worktree>("move_worktree", { repoPath, worktreePath, newPath });
+}
+
+export async function renameWorktreeBranch(
+  worktreePath: string,
+  newBranchName: string
+): Promise<void> {
+  return invoke<void>("rename_worktree_branch", {
+    worktreePath,
+    newBranchName,
+  });
+}
+
+export async function pruneWorktrees(
+  repoPath: string,
+  dryRun = false
+): Promise<Worktree[]> {
+  return invoke<Worktree[]>("prune_worktrees", { repoPath, dryRun });
+}
+
+export async function repairWorktrees(
+  repoPath: string,
+  paths: string[] = []
+): Promise<void> {
+  return invoke<void>("repair_worktrees", { repoPath, paths });
 }
 
 export async function mergeBranch(
   repoPath: string,
   sourceBranch: string,
-  targetBranch: string
+  targetBranch: string,
+  strategy: MergeStrategy = "merge"
 ): Promise<MergeResult> {
   return invoke<MergeResult>("merge_branch", {
     repoPath,
     sourceBranch,
     targetBranch,
+    strategy,
   });
 }
 
@@ -80,6 +467,416 @@ export async function getBranchInfo(
   return invoke<BranchInfo>("get_branch_info", { repoPath, branchName });
 }
 
+// Branch commands
+export async function listBranches(repoPath: string): Promise<Branch[]> {
+  return invoke<Branch[]>("list_branches", { repoPath });
+}
+
+export async function listArchivedBranches(
+  repoPath: string
+): Promise<ArchivedBranch[]> {
+  return invoke<ArchivedBranch[]>("list_archived_branches", { repoPath });
+}
+
+export async function restoreArchivedBranch(
+  repoPath: string,
+  refName: string,
+  branchName?: string
+): Promise<string> {
+  return invoke<string>("restore_archived_branch", {
+    repoPath,
+    refName,
+    branchName,
+  });
+}
+
+export async function deleteArchivedBranch(
+  repoPath: string,
+  refName: string
+): Promise<void> {
+  return invoke<void>("delete_archived_branch", { repoPath, refName });
+}
+
+// Status commands
+export async function getWorktreeStatus(
+  worktreePath: string,
+  includeIgnored = false
+): Promise<FileStatus[]> {
+  return invoke<FileStatus[]>("get_worktree_status", {
+    worktreePath,
+    includeIgnored,
+  });
+}
+
+// Diff commands
+export async function getDiff(
+  worktreePath: string,
+  target: DiffTarget,
+  paths?: string[],
+  includeContent = false
+): Promise<FileDiff[]> {
+  return invoke<FileDiff[]>("get_diff", {
+    worktreePath,
+    target,
+    paths,
+    includeContent,
+  });
+}
+
+// Commit commands
+export async function stageFiles(
+  worktreePath: string,
+  paths: string[]
+): Promise<void> {
+  return invoke<void>("stage_files", { worktreePath, paths });
+}
+
+export async function unstageFiles(
+  worktreePath: string,
+  paths: string[]
+): Promise<void> {
+  return invoke<void>("unstage_files", { worktreePath, paths });
+}
+
+export async function stageLines(
+  worktreePath: string,
+  selection: PatchSelection
+): Promise<void> {
+  return invoke<void>("stage_lines", { worktreePath, selection });
+}
+
+export async function unstageLines(
+  worktreePath: string,
+  selection: PatchSelection
+): Promise<void> {
+  return invoke<void>("unstage_lines", { worktreePath, selection });
+}
+
+export async function commitChanges(
+  worktreePath: string,
+  message: string | null,
+  amend = false,
+  signOff = false
+): Promise<CommitInfo> {
+  return invoke<CommitInfo>("commit_changes", {
+    worktreePath,
+    message,
+    amend,
+    signOff,
+  });
+}
+
+// Conflict commands
+export async function getConflicts(
+  worktreePath: string
+): Promise<ConflictFile[]> {
+  return invoke<ConflictFile[]>("get_conflicts", { worktreePath });
+}
+
+export async function acceptConflictSide(
+  worktreePath: string,
+  path: string,
+  side: ConflictSide
+): Promise<void> {
+  return invoke<void>("accept_conflict_side", { worktreePath, path, side });
+}
+
+export async function saveConflictResolution(
+  worktreePath: string,
+  path: string,
+  content: string
+): Promise<void> {
+  return invoke<void>("save_conflict_resolution", {
+    worktreePath,
+    path,
+    content,
+  });
+}
+
+export async function markResolved(
+  worktreePath: string,
+  paths: string[]
+): Promise<void> {
+  return invoke<void>("mark_resolved", { worktreePath, paths });
+}
+
+export async function concludeMerge(
+  worktreePath: string,
+  message?: string
+): Promise<MergeResult> {
+  return invoke<MergeResult>("conclude_merge", { worktreePath, message });
+}
+
+export async function abortMerge(worktreePath: string): Promise<void> {
+  return invoke<void>("abort_merge", { worktreePath });
+}
+
+// Log commands
+export async function getCommitLog(
+  worktreePath: string,
+  revisions?: string[],
+  skip = 0,
+  limit?: number
+): Promise<LogEntry[]> {
+  return invoke<LogEntry[]>("get_commit_log", {
+    worktreePath,
+    revisions,
+    skip,
+    limit,
+  });
+}
+
+// Carry commands
+export async function getCarryConfig(repoPath: string): Promise<CarryConfig> {
+  return invoke<CarryConfig>("get_carry_config", { repoPath });
+}
+
+export async function setCarryConfig(
+  repoPath: string,
+  config: CarryConfig
+): Promise<void> {
+  return invoke<void>("set_carry_config", { repoPath, config });
+}
+
+export async function carryFiles(
+  sourcePath: string,
+  worktreePath: string
+): Promise<string[]> {
+  return invoke<string[]>("carry_files", { sourcePath, worktreePath });
+}
+
+// Provisioning commands
+export async function getProvisionRecipe(
+  repoPath: string
+): Promise<ProvisionRecipe> {
+  return invoke<ProvisionRecipe>("get_provision_recipe", { repoPath });
+}
+
+export async function setProvisionRecipe(
+  repoPath: string,
+  recipe: ProvisionRecipe
+): Promise<void> {
+  return invoke<void>("set_provision_recipe", { repoPath, recipe });
+}
+
+export async function runProvisioning(worktreePath: string): Promise<boolean> {
+  return invoke<boolean>("run_provisioning", { worktreePath });
+}
+
+export async function cancelProvisioning(worktreePath: string): Promise<void> {
+  return invoke<void>("cancel_provisioning", { worktreePath });
+}
+
+export async function getProvisionStatus(
+  worktreePath: string
+): Promise<ProvisionRecord | null> {
+  return invoke<ProvisionRecord | null>("get_provision_status", {
+    worktreePath,
+  });
+}
+
+// Watcher commands
+export async function watchRepository(repoPath: string): Promise<void> {
+  return invoke<void>("watch_repository", { repoPath });
+}
+
+export async function unwatchRepository(repoPath: string): Promise<void> {
+  return invoke<void>("unwatch_repository", { repoPath });
+}
+
+// Lineage commands
+export async function getWorktreeGraph(
+  repoPath: string
+): Promise<LinkedWorktree[]> {
+  return invoke<LinkedWorktree[]>("get_worktree_graph", { repoPath });
+}
+
+export async function setWorktreeParent(
+  repoPath: string,
+  branchName: string,
+  parentBranch: string | null
+): Promise<void> {
+  return invoke<void>("set_worktree_parent", {
+    repoPath,
+    branchName,
+    parentBranch,
+  });
+}
+
+export async function getAheadBehindMatrix(
+  repoPath: string
+): Promise<EdgeDivergence[]> {
+  return invoke<EdgeDivergence[]>("get_ahead_behind_matrix", { repoPath });
+}
+
+// Merge preview commands
+export async function previewMerge(
+  repoPath: string,
+  sourceBranch: string,
+  targetBranch: string
+): Promise<MergePreview> {
+  return invoke<MergePreview>("preview_merge", {
+    repoPath,
+    sourceBranch,
+    targetBranch,
+  });
+}
+
+// Rebase commands
+export async function rebaseWorktree(
+  worktreePath: string,
+  onto?: string
+): Promise<MergeResult> {
+  return invoke<MergeResult>("rebase_worktree", { worktreePath, onto });
+}
+
+export async function continueRebase(
+  worktreePath: string
+): Promise<MergeResult> {
+  return invoke<MergeResult>("continue_rebase", { worktreePath });
+}
+
+export async function skipRebase(worktreePath: string): Promise<MergeResult> {
+  return invoke<MergeResult>("skip_rebase", { worktreePath });
+}
+
+export async function abortRebase(worktreePath: string): Promise<void> {
+  return invoke<void>("abort_rebase", { worktreePath });
+}
+
+// Restack commands
+export async function restack(
+  repoPath: string,
+  branchName: string
+): Promise<RestackResult> {
+  return invoke<RestackResult>("restack", { repoPath, branchName });
+}
+
+export async function continueRestack(
+  repoPath: string
+): Promise<RestackResult> {
+  return invoke<RestackResult>("continue_restack", { repoPath });
+}
+
+export async function abortRestack(repoPath: string): Promise<void> {
+  return invoke<void>("abort_restack", { repoPath });
+}
+
+// Remote commands
+export async function fetchRemote(
+  repoPath: string,
+  remote?: string,
+  prune?: boolean
+): Promise<void> {
+  return invoke<void>("fetch_remote", { repoPath, remote, prune });
+}
+
+export async function pullBranch(
+  worktreePath: string,
+  rebase?: boolean
+): Promise<MergeResult> {
+  return invoke<MergeResult>("pull_branch", { worktreePath, rebase });
+}
+
+export async function pushBranch(
+  worktreePath: string,
+  remote?: string,
+  setUpstream?: boolean,
+  forceWithLease?: boolean
+): Promise<void> {
+  return invoke<void>("push_branch", {
+    worktreePath,
+    remote,
+    setUpstream,
+    forceWithLease,
+  });
+}
+
+export async function answerCredentialPrompt(
+  id: number,
+  response: string | null
+): Promise<void> {
+  return invoke<void>("answer_credential_prompt", { id, response });
+}
+
+// Cherry-pick commands
+export async function cherryPick(
+  worktreePath: string,
+  commits: string[],
+  recordOrigin?: boolean
+): Promise<MergeResult> {
+  return invoke<MergeResult>("cherry_pick", {
+    worktreePath,
+    commits,
+    recordOrigin,
+  });
+}
+
+export async function continueCherryPick(
+  worktreePath: string
+): Promise<MergeResult> {
+  return invoke<MergeResult>("continue_cherry_pick", { worktreePath });
+}
+
+export async function skipCherryPick(
+  worktreePath: string
+): Promise<MergeResult> {
+  return invoke<MergeResult>("skip_cherry_pick", { worktreePath });
+}
+
+export async function abortCherryPick(worktreePath: string): Promise<void> {
+  return invoke<void>("abort_cherry_pick", { worktreePath });
+}
+
+// Stash commands
+export async function listStashes(repoPath: string): Promise<StashEntry[]> {
+  return invoke<StashEntry[]>("list_stashes", { repoPath });
+}
+
+export async function pushStash(
+  worktreePath: string,
+  message?: string,
+  includeUntracked?: boolean,
+  paths?: string[]
+): Promise<StashEntry | null> {
+  return invoke<StashEntry | null>("push_stash", {
+    worktreePath,
+    message,
+    includeUntracked,
+    paths,
+  });
+}
+
+export async function applyStash(
+  worktreePath: string,
+  stash: string
+): Promise<MergeResult> {
+  return invoke<MergeResult>("apply_stash", { worktreePath, stash });
+}
+
+export async function popStash(
+  worktreePath: string,
+  stash: string
+): Promise<MergeResult> {
+  return invoke<MergeResult>("pop_stash", { worktreePath, stash });
+}
+
+export async function dropStash(repoPath: string, stash: string): Promise<void> {
+  return invoke<void>("drop_stash", { repoPath, stash });
+}
+
+export async function transferStash(
+  stash: string,
+  targetWorktreePath: string,
+  keep?: boolean
+): Promise<MergeResult> {
+  return invoke<MergeResult>("transfer_stash", {
+    stash,
+    targetWorktreePath,
+    keep,
+  });
+}
+
 // Filesystem types
 export interface FileEntry {
   path: string;
@@ -137,3 +934,38 @@ export async function onPtyOutput(
     callback(event.payload);
   });
 }
+
+// Provisioning event listener
+export async function onProvisionEvent(
+  callback: (event: ProvisionEvent) => void
+): Promise<UnlistenFn> {
+  return listen<ProvisionEvent>("provision-event", (event) => {
+    callback(event.payload);
+  });
+}
+
+// Repository event listener
+export async function onRepositoryEvent(
+  callback: (event: RepositoryEvent) => void
+): Promise<UnlistenFn> {
+  return listen<RepositoryEvent>("repository-event", (event) => {
+    callback(event.payload);
+  });
+}
+
+// Remote event listeners
+export async function onRemoteProgress(
+  callback: (event: RemoteProgress) => void
+): Promise<UnlistenFn> {
+  return listen<RemoteProgress>("remote-progress", (event) => {
+    callback(event.payload);
+  });
+}
+
+export async function onCredentialPrompt(
+  callback: (event: CredentialPrompt) => void
+): Promise<UnlistenFn> {
+  return listen<CredentialPrompt>("credential-prompt", (event) => {
+    callback(event.payload);
+  });
+}
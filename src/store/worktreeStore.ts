@@ -1,13 +1,21 @@
 import { create } from "zustand";
 import { immer } from "zustand/middleware/immer";
 import {
-  listWorktrees,
+  getWorktreeGraph,
+  setWorktreeParent,
   createWorktree,
   removeWorktree,
+  moveWorktree,
+  renameWorktreeBranch,
+  listBranches,
   type Worktree,
 } from "@/lib/tauri";
 import { isTauri } from "@/lib/environment";
-import { loadFromStorage, saveToStorage } from "@/lib/storage";
+import {
+  loadFromStorage,
+  saveToStorage,
+  removeFromStorage,
+} from "@/lib/storage";
 
 /**
  * Generate a stable ID from a worktree path.
@@ -26,18 +34,70 @@ function generateStableId(path: string): string {
   return "wt-" + Math.abs(hash).toString(36);
 }
 
-// Storage key for parent relationships
+/**
+ * ID of a worktree that survives moves: linked worktrees are identified by
+ * their administrative name, which `git worktree move` keeps.
+ */
+function worktreeId(worktree: Worktree, mainPath: string): string {
+  return generateStableId(
+    worktree.name ? `${mainPath}#${worktree.name}` : worktree.path
+  );
+}
+
+// Legacy storage key for parent relationships, now persisted by the backend
 const PARENT_RELATIONSHIPS_KEY = "codecraft-parent-relationships";
 
 // Store branch -> parent branch relationships
 type ParentRelationships = Record<string, string>; // branchName -> parentBranchName
 
-function loadParentRelationships(): ParentRelationships {
-  return loadFromStorage(PARENT_RELATIONSHIPS_KEY, {});
-}
+// Repositories already migrated since the app started
+const migratedRepositories = new Set<string>();
+
+/**
+ * Move parent relationships saved by older versions into the repository's
+ * backend lineage. The old map is shared by every repository, so only
+ * entries for branches of this one are moved and dropped from it; the rest
+ * stay for their own repositories.
+ */
+async function migrateParentRelationships(repoPath: string): Promise<void> {
+  if (migratedRepositories.has(repoPath)) return;
+  migratedRepositories.add(repoPath);
 
-function saveParentRelationships(relationships: ParentRelationships): void {
-  saveToStorage(PARENT_RELATIONSHIPS_KEY, relationships);
+  const relationships = loadFromStorage<ParentRelationships>(
+    PARENT_RELATIONSHIPS_KEY,
+    {}
+  );
+  if (Object.keys(relationships).length === 0) return;
+
+  let branches: Set<string>;
+  try {
+    branches = new Set(
+      (await listBranches(repoPath))
+        .filter((branch) => !branch.is_remote)
+        .map((branch) => branch.name)
+    );
+  } catch (error) {
+    // Try again on the next fetch
+    migratedRepositories.delete(repoPath);
+    throw error;
+  }
+
+  const remaining: ParentRelationships = { ...relationships };
+  for (const [branchName, parentBranch] of Object.entries(relationships)) {
+    if (!branches.has(branchName)) continue;
+    try {
+      await setWorktreeParent(repoPath, branchName, parentBranch);
+      delete remaining[branchName];
+    } catch (error) {
+      console.warn("Failed to migrate parent of", branchName, error);
+    }
+  }
+
+  if (Object.keys(remaining).length === 0) {
+    removeFromStorage(PARENT_RELATIONSHIPS_KEY);
+  } else {
+    saveToStorage(PARENT_RELATIONSHIPS_KEY, remaining);
+  }
 }
 
 export interface WorktreeNode extends Worktree {
@@ -63,6 +123,11 @@ const MOCK_WORKTREES: WorktreeNode[] = [
     is_bare: false,
     is_detached: false,
     is_main: true,
+    name: null,
+    is_locked: false,
+    lock_reason: null,
+    is_prunable: false,
+    prunable_reason: null,
     parentId: null,
   },
   {
@@ -73,6 +138,11 @@ const MOCK_WORKTREES: WorktreeNode[] = [
     is_bare: false,
     is_detached: false,
     is_main: false,
+    name: null,
+    is_locked: false,
+    lock_reason: null,
+    is_prunable: false,
+    prunable_reason: null,
     parentId: generateStableId(MOCK_PATHS.main),
   },
   {
@@ -83,6 +153,11 @@ const MOCK_WORKTREES: WorktreeNode[] = [
     is_bare: false,
     is_detached: false,
     is_main: false,
+    name: null,
+    is_locked: false,
+    lock_reason: null,
+    is_prunable: false,
+    prunable_reason: null,
     parentId: generateStableId(MOCK_PATHS.main),
   },
   {
@@ -93,6 +168,11 @@ const MOCK_WORKTREES: WorktreeNode[] = [
     is_bare: false,
     is_detached: false,
     is_main: false,
+    name: null,
+    is_locked: false,
+    lock_reason: null,
+    is_prunable: false,
+    prunable_reason: null,
     parentId: generateStableId(MOCK_PATHS.feature),
   },
 ];
@@ -112,6 +192,8 @@ interface WorktreeState {
     path: string
   ) => Promise<void>;
   deleteWorktree: (worktreePath: string, force?: boolean) => Promise<void>;
+  relocateWorktree: (worktreePath: string, newPath: string) => Promise<void>;
+  renameBranch: (worktreePath: string, newBranchName: string) => Promise<void>;
   setError: (error: string | null) => void;
   clearError: () => void;
 }
@@ -142,46 +224,22 @@ export const useWorktreeStore = create<WorktreeState>()(
         const mainPath = repoPath || get().mainRepoPath || ".";
         set({ mainRepoPath: mainPath });
 
-        const worktrees = await listWorktrees(mainPath);
+        await migrateParentRelationships(mainPath);
+        const worktrees = await getWorktreeGraph(mainPath);
 
-        // Convert to WorktreeNode with stable IDs based on path
+        // Convert to WorktreeNode with stable IDs; the backend already
+        // resolved each worktree's parent from the stored lineage
+        const mainWorktreePath =
+          worktrees.find((wt) => wt.is_main)?.path ?? mainPath;
+        const ids = new Map(
+          worktrees.map((wt) => [wt.path, worktreeId(wt, mainWorktreePath)])
+        );
         const worktreeNodes: WorktreeNode[] = worktrees.map((wt) => ({
           ...wt,
-          id: generateStableId(wt.path),
-          parentId: null,
+          id: ids.get(wt.path)!,
+          parentId: wt.parent_path ? ids.get(wt.parent_path) ?? null : null,
         }));
 
-        // Load stored parent relationships
-        const parentRelationships = loadParentRelationships();
-
-        // Build a map of branch -> worktree node for lookup
-        const branchToNode = new Map<string, WorktreeNode>();
-        worktreeNodes.forEach((wt) => {
-          if (wt.branch) {
-            branchToNode.set(wt.branch, wt);
-          }
-        });
-
-        // Find parent relationships based on stored data or default to main
-        const mainWorktree = worktreeNodes.find((wt) => wt.is_main);
-        worktreeNodes.forEach((wt) => {
-          if (!wt.is_main && wt.branch) {
-            // Look up stored parent relationship
-            const parentBranch = parentRelationships[wt.branch];
-            if (parentBranch) {
-              const parentNode = branchToNode.get(parentBranch);
-              if (parentNode) {
-                wt.parentId = parentNode.id;
-                return;
-              }
-            }
-            // Default to main if no stored relationship or parent not found
-            if (mainWorktree) {
-              wt.parentId = mainWorktree.id;
-            }
-          }
-        });
-
         set({ worktrees: worktreeNodes, loading: false });
       } catch (error) {
         set({
@@ -198,11 +256,6 @@ export const useWorktreeStore = create<WorktreeState>()(
     ) => {
       set({ loading: true, error: null });
 
-      // Store the parent relationship before creating
-      const parentRelationships = loadParentRelationships();
-      parentRelationships[branchName] = baseBranch;
-      saveParentRelationships(parentRelationships);
-
       // Mock mode for browser development
       if (!isTauri) {
         await new Promise((resolve) => setTimeout(resolve, 500));
@@ -216,6 +269,11 @@ export const useWorktreeStore = create<WorktreeState>()(
           is_bare: false,
           is_detached: false,
           is_main: false,
+          name: null,
+          is_locked: false,
+          lock_reason: null,
+          is_prunable: false,
+          prunable_reason: null,
           parentId: parent?.id || null,
         };
         set((state) => {
@@ -247,15 +305,6 @@ export const useWorktreeStore = create<WorktreeState>()(
     deleteWorktree: async (worktreePath: string, force = false) => {
       set({ loading: true, error: null });
 
-      // Find the branch name to clean up parent relationship
-      const { worktrees } = get();
-      const worktreeToDelete = worktrees.find((wt) => wt.path === worktreePath);
-      if (worktreeToDelete?.branch) {
-        const parentRelationships = loadParentRelationships();
-        delete parentRelationships[worktreeToDelete.branch];
-        saveParentRelationships(parentRelationships);
-      }
-
       // Mock mode for browser development
       if (!isTauri) {
         await new Promise((resolve) => setTimeout(resolve, 300));
@@ -283,6 +332,60 @@ export const useWorktreeStore = create<WorktreeState>()(
       }
     },
 
+    relocateWorktree: async (worktreePath: string, newPath: string) => {
+      set({ loading: true, error: null });
+
+      // Mock mode for browser development
+      if (!isTauri) {
+        set((state) => {
+          const worktree = state.worktrees.find((wt) => wt.path === worktreePath);
+          if (worktree) worktree.path = newPath;
+          state.loading = false;
+        });
+        return;
+      }
+
+      const { mainRepoPath } = get();
+      if (!mainRepoPath) {
+        set({ error: "No repository path set", loading: false });
+        return;
+      }
+
+      try {
+        await moveWorktree(mainRepoPath, worktreePath, newPath);
+        await get().fetchWorktrees();
+      } catch (error) {
+        set({
+          error: error instanceof Error ? error.message : String(error),
+          loading: false,
+        });
+      }
+    },
+
+    renameBranch: async (worktreePath: string, newBranchName: string) => {
+      set({ loading: true, error: null });
+
+      // Mock mode for browser development
+      if (!isTauri) {
+        set((state) => {
+          const worktree = state.worktrees.find((wt) => wt.path === worktreePath);
+          if (worktree) worktree.branch = newBranchName;
+          state.loading = false;
+        });
+        return;
+      }
+
+      try {
+        await renameWorktreeBranch(worktreePath, newBranchName);
+        await get().fetchWorktrees();
+      } catch (error) {
+        set({
+          error: error instanceof Error ? error.message : String(error),
+          loading: false,
+        });
+      }
+    },
+
     setError: (error) => set({ error }),
     clearError: () => set({ error: null }),
   }))